@@ -0,0 +1,121 @@
+// Command Line Arguments
+
+use crate::{
+    error::{AppResult, ErrorType},
+    fs::FileEncoding
+};
+
+/// The arguments passed to this app.
+#[derive(Debug, Default)]
+pub struct AppArgs {
+    pub paths: Vec<String>,     // "-" means reading from stdin
+    pub encoding: Option<FileEncoding>,
+    pub syntax: Option<String>,
+    pub stdout: bool,           // Whether saving writes into stdout
+    pub readonly: bool
+}
+
+impl AppArgs {
+    /// Parse arguments without the program name.
+    pub fn parse<I>(args: I) -> AppResult<Self>
+    where I: IntoIterator<Item = String>
+    {
+        let mut app_args = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (option, value) = match arg.split_once('=') {
+                Some((option, value)) if arg.starts_with("--") => {
+                    (option.to_owned(), Some(value.to_owned()))
+                },
+                _ => (arg, None)
+            };
+
+            match option.as_str() {
+                "--encoding" => {
+                    let label = Self::option_value(&option, value, &mut args)?;
+
+                    app_args.encoding = match FileEncoding::from_label(&label) {
+                        Some(encoding) => Some(encoding),
+                        None => return Err(
+                            ErrorType::Specific(
+                                format!("Unknown encoding: {}", label)
+                            ).pack()
+                        ),
+                    };
+                },
+
+                "--syntax" => app_args.syntax = Some(
+                    Self::option_value(&option, value, &mut args)?
+                ),
+
+                "--stdout" => app_args.stdout = true,
+
+                "-R" | "--readonly" => app_args.readonly = true,
+
+                _option if _option.starts_with("-") && _option != "-" => return Err(
+                    ErrorType::Specific(
+                        format!("Unknown option: {}", _option)
+                    ).pack()
+                ),
+
+                _ => app_args.paths.push(option)
+            }
+        }
+
+        if app_args.paths.is_empty() {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Wrong arguments for this app!")
+                ).pack()
+            )
+        }
+
+        Ok(app_args)
+    }
+
+    /// Get the value of option, which is after '=' or the next argument.
+    fn option_value<I>(option: &str, value: Option<String>, args: &mut I) -> AppResult<String>
+    where I: Iterator<Item = String>
+    {
+        match value.or_else(|| args.next()) {
+            Some(value) => Ok(value),
+            None => Err(
+                ErrorType::Specific(
+                    format!("Missing value for option {}", option)
+                ).pack()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> AppResult<AppArgs> {
+        AppArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_test() {
+        let args = parse(&["--encoding", "gbk", "test.txt"]).unwrap();
+        assert_eq!(args.paths, ["test.txt"]);
+        assert_eq!(args.encoding.unwrap().name(), "GBK");
+
+        let args = parse(&["test.txt", "--encoding=latin1"]).unwrap();
+        assert_eq!(args.encoding.unwrap().name(), "windows-1252");
+
+        let args = parse(&["-", "--syntax=json", "--stdout", "-R"]).unwrap();
+        assert_eq!(args.paths, ["-"]);
+        assert_eq!(args.syntax.unwrap(), "json");
+        assert!(args.stdout && args.readonly);
+
+        let args = parse(&["a.rs", "b.rs", "c.toml"]).unwrap();
+        assert_eq!(args.paths, ["a.rs", "b.rs", "c.toml"]);
+
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["test.txt", "--encoding"]).is_err());
+        assert!(parse(&["test.txt", "--encoding", "none"]).is_err());
+    }
+}
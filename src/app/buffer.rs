@@ -0,0 +1,162 @@
+// Buffer List
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use crate::{
+    error::AppResult,
+    fs::{FileState, Position},
+    ui::EditorState,
+    utils::grapheme_len
+};
+
+use super::{AppArgs, SearchIndicates};
+
+/// The keys used to pick buffer from buffer list.
+pub const BUFFER_KEYS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+
+/// An opened file with its own editing state.
+#[derive(Debug)]
+pub struct Buffer {
+    pub file_state: FileState,
+    pub editor_state: EditorState,
+    pub search_result: Arc<Mutex<SearchIndicates>>
+}
+
+/// All the opened buffers.
+/// The active buffer is kept in App, thus its slot here is empty.
+#[derive(Debug)]
+pub struct BufferList {
+    slots: Vec<Option<Buffer>>,
+    active: usize
+}
+
+impl Buffer {
+    /// Open the file at `path` with the options from command line arguments.
+    pub async fn open(path: String, args: &AppArgs) -> AppResult<Self> {
+        let mut file_state = FileState::default();
+        file_state.init(path, args.encoding).await?;
+
+        if let Some(ref syntax) = args.syntax {
+            file_state.set_syntax(syntax.to_owned())?;
+        }
+
+        if args.stdout {
+            file_state.write_to_stdout();
+        }
+
+        if args.readonly || !file_state.writable().await {
+            file_state.set_readonly(true);
+        }
+
+        let mut editor_state = EditorState::default();
+        editor_state.update_linenr(file_state.content_ref().lock().await.len());
+
+        Ok(Self {
+            file_state,
+            editor_state,
+            search_result: Arc::new(Mutex::new(SearchIndicates::default()))
+        })
+    }
+
+    /// Move the cursor to the remembered position, which is limited within the content.
+    pub async fn restore(&mut self, position: &Position) {
+        // The position in text view is meaningless for hex view.
+        if self.file_state.hex().is_some() {
+            return
+        }
+
+        let content = self.file_state.content_ref().lock().await;
+        let line = position.cursor.1.min(content.len() - 1);
+        let col = position.cursor.0.min(grapheme_len(&content.line(line)) - 1);
+
+        *self.editor_state.cursor_mut() = (col, line);
+        *self.editor_state.offset_mut() = position.offset.min(line);
+
+        if let Some(ref pattern) = position.pattern {
+            self.search_result.lock().await.set(
+                pattern.to_owned(),
+                SearchIndicates::find(content.lines(), pattern).into_iter()
+            );
+        }
+    }
+}
+
+/// Get the position of buffer to be remembered, which is None when it's not a saved file.
+pub async fn buffer_position(
+    file_state: &FileState,
+    editor_state: &EditorState,
+    search_result: &Mutex<SearchIndicates>
+) -> Option<(PathBuf, Position)>
+{
+    if file_state.hex().is_some() || file_state.is_new_file() {
+        return None
+    }
+
+    let search_result = search_result.lock().await;
+    let pattern = if search_result.has_history() {
+        Some(search_result.target().to_owned())
+    } else {
+        None
+    };
+
+    Some((file_state.path()?.to_path_buf(), Position {
+        cursor: editor_state.cursor(),
+        offset: editor_state.offset(),
+        pattern: pattern.filter(|pattern| !pattern.is_empty())
+    }))
+}
+
+impl Default for BufferList {
+    fn default() -> Self {
+        Self { slots: vec![None], active: 0 }
+    }
+}
+
+impl BufferList {
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Append an inactive buffer.
+    pub fn push(&mut self, buffer: Buffer) {
+        self.slots.push(Some(buffer));
+    }
+
+    /// Take the inactive buffer at `idx` to be active.
+    pub fn take(&mut self, idx: usize) -> Buffer {
+        self.slots[idx].take()
+            .expect("Error code 1 at take in buffer.rs!")
+    }
+
+    /// Store the previous active buffer into its slot, and mark the slot at `idx` as active.
+    pub fn set_active(&mut self, idx: usize, previous: Buffer) {
+        self.slots[self.active] = Some(previous);
+        self.active = idx;
+    }
+
+    /// Remove the slot of active buffer, and make its neighbor active.
+    /// Return the buffer to be active.
+    pub fn remove_active(&mut self) -> Buffer {
+        self.slots.remove(self.active);
+        self.active = self.active.min(self.slots.len() - 1);
+
+        self.slots[self.active].take()
+            .expect("Error code 1 at remove_active in buffer.rs!")
+    }
+
+    /// Iterate over the slots, the one of active buffer is None.
+    pub fn slots(&self) -> impl Iterator<Item = Option<&Buffer>> {
+        self.slots.iter().map(Option::as_ref)
+    }
+
+    pub fn slots_mut(&mut self) -> impl Iterator<Item = Option<&mut Buffer>> {
+        self.slots.iter_mut().map(Option::as_mut)
+    }
+}
@@ -0,0 +1,163 @@
+// Clipboard
+
+use std::io::{stderr, Write};
+use std::process::Stdio;
+
+use tokio::{io::AsyncWriteExt, process};
+
+use crate::{
+    config::ClipboardProvider,
+    error::{AppError, AppResult, ErrorType}
+};
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The system clipboard accessed with the provider set in config.
+#[derive(Debug, Default)]
+pub struct Clipboard {
+    // The text copied lastly, which is pasted when the provider cannot paste.
+    internal: Option<String>
+}
+
+impl Clipboard {
+    pub async fn copy(&mut self, provider: &ClipboardProvider, text: String) -> AppResult<()> {
+        match provider {
+            ClipboardProvider::Osc52 => {
+                let tmux = std::env::var_os("TMUX").is_some();
+
+                // The terminal UI is drawn on stderr.
+                let mut terminal = stderr();
+                terminal.write_all(osc52_sequence(&text, tmux).as_bytes())?;
+                terminal.flush()?;
+            },
+            ClipboardProvider::Command { copy, .. } => run_copy(copy, &text).await?,
+            ClipboardProvider::Internal => (),
+        }
+
+        self.internal = Some(text);
+
+        Ok(())
+    }
+
+    pub async fn paste(&self, provider: &ClipboardProvider) -> AppResult<Option<String>> {
+        if let ClipboardProvider::Command { paste: Some(paste), .. } = provider {
+            return Ok(Some(run_paste(paste).await?))
+        }
+
+        Ok(self.internal.to_owned())
+    }
+}
+
+/// Write `text` into the stdin of copy command.
+async fn run_copy(command: &[String], text: &str) -> AppResult<()> {
+    // The output of command would break the terminal UI.
+    let mut child = process::Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| command_error(command, err))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).await?;
+    }
+
+    if !child.wait().await?.success() {
+        return Err(command_error(command, "exited with failure"))
+    }
+
+    Ok(())
+}
+
+/// Read the stdout of paste command.
+async fn run_paste(command: &[String]) -> AppResult<String> {
+    let output = process::Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .map_err(|err| command_error(command, err))?;
+
+    if !output.status.success() {
+        return Err(command_error(command, "exited with failure"))
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn command_error(command: &[String], err: impl std::fmt::Display) -> AppError {
+    ErrorType::Specific(
+        format!("Failed to run clipboard command {}: {}", command[0], err)
+    ).pack()
+}
+
+/// Make the OSC 52 sequence setting system clipboard, which is passed through by tmux.
+fn osc52_sequence(text: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+
+    if tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter()
+            .enumerate()
+            .fold(0u32, |group, (idx, byte)| group | (*byte as u32) << (16 - idx * 8));
+
+        for idx in 0..4 {
+            if idx > chunk.len() {
+                encoded.push('=');
+            } else {
+                encoded.push(BASE64_CHARS[(group >> (18 - idx * 6)) as usize & 0x3f] as char);
+            }
+        }
+    }
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn clipboard_test() {
+        assert_eq!(base64_encode(b"tetor"), "dGV0b3I=");
+        assert_eq!(base64_encode(b"ab"), "YWI=");
+        assert_eq!(base64_encode(b"abc"), "YWJj");
+        assert_eq!(osc52_sequence("a", false), "\x1b]52;c;YQ==\x07");
+        assert_eq!(osc52_sequence("a", true), "\x1bPtmux;\x1b\x1b]52;c;YQ==\x07\x1b\\");
+
+        // The stub commands keep the clipboard in a temporary file.
+        let dir = TempDir::new("clipboard_test");
+        let path = dir.join("clipboard");
+        let provider = ClipboardProvider::Command {
+            copy: vec![String::from("sh"), String::from("-c"), format!("cat > '{}'", path.display())],
+            paste: Some(vec![String::from("cat"), path.to_string_lossy().into_owned()])
+        };
+
+        let mut clipboard = Clipboard::default();
+        clipboard.copy(&provider, String::from("foo\nbar")).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "foo\nbar");
+
+        std::fs::write(&path, "from others").unwrap();
+        assert_eq!(clipboard.paste(&provider).await.unwrap().as_deref(), Some("from others"));
+        assert_eq!(
+            clipboard.paste(&ClipboardProvider::Internal).await.unwrap().as_deref(),
+            Some("foo\nbar")
+        );
+
+        let failing = ClipboardProvider::Command { copy: vec![String::from("false")], paste: None };
+        assert!(clipboard.copy(&failing, String::new()).await.is_err());
+    }
+}
@@ -4,14 +4,65 @@ use tokio::runtime::Runtime;
 use ratatui::crossterm::event::KeyCode;
 
 use crate::{
-    command::{backward_char, insert_char, Command, CommandPrior},
+    command::{backward_char, hex_input, insert_char, Command, CommandPrior},
     error::{AppResult, ErrorType},
     ui::{CommandEdit, ModalType}
 };
 
 use super::App;
 
+const MAX_COUNT: usize = 999_999;
+
 pub fn handle_input(app: &mut App, key: KeyCode, rt: &Runtime) -> AppResult<()> {
+    // The count can also be typed after the operator, like `d2w`.
+    let waiting_command = app.editor_state.modal.modal() == ModalType::Normal &&
+        matches!(
+            app.prior_command,
+            CommandPrior::None | CommandPrior::Delete | CommandPrior::Change
+        ) &&
+        app.command_edit == CommandEdit::None;
+
+    if waiting_command && count_digit(app, key) {
+        return Ok(())
+    }
+
+    app.file_state.begin_step(app.editor_state.cursor());
+
+    let result = dispatch_key(app, key, rt);
+
+    // Each normal command & each whole insert session is an undo step.
+    if app.editor_state.modal.modal() == ModalType::Normal &&
+        app.prior_command == CommandPrior::None &&
+        app.command_edit == CommandEdit::None
+    {
+        app.file_state.commit_step(app.editor_state.cursor());
+    }
+
+    // The count is kept for the keys after a prior command.
+    if app.prior_command == CommandPrior::None && app.command_edit == CommandEdit::None {
+        app.count = None;
+    }
+
+    result
+}
+
+/// Add the digit to count, `0` is only a digit after other digits.
+fn count_digit(app: &mut App, key: KeyCode) -> bool {
+    let digit = match key {
+        KeyCode::Char(_key) if _key.is_ascii_digit() => _key.to_digit(10).unwrap() as usize,
+        _ => return false,
+    };
+
+    if digit == 0 && app.count.is_none() {
+        return false
+    }
+
+    app.count = Some((app.count.unwrap_or(0) * 10 + digit).min(MAX_COUNT));
+
+    true
+}
+
+fn dispatch_key(app: &mut App, key: KeyCode, rt: &Runtime) -> AppResult<()> {
     if app.prior_command == CommandPrior::ConfirmError {
         app.prior_command = CommandPrior::None;
         app.app_errors.throw();
@@ -38,6 +89,18 @@ pub fn handle_input(app: &mut App, key: KeyCode, rt: &Runtime) -> AppResult<()>
     // ))?;
 
     if app.get_modal().modal() == ModalType::Insert {
+        // Bytes are overwritten in hex view.
+        if app.file_state.hex().is_some() {
+            if key == KeyCode::Esc {
+                app.get_modal().switch_normal();
+                return Ok(())
+            }
+
+            app.update_stylized = rt.block_on(hex_input(app, key))?;
+
+            return Ok(())
+        }
+
         app.update_stylized = match key {
             KeyCode::Char(_key) => rt.block_on(insert_char(app, _key))?,
             KeyCode::Backspace => rt.block_on(backward_char(app))?,
@@ -57,11 +120,25 @@ pub fn handle_input(app: &mut App, key: KeyCode, rt: &Runtime) -> AppResult<()>
         let prior_command = match app.prior_command {
             CommandPrior::None            => None,
             CommandPrior::Mark            => Some(Command::Mark),
+            CommandPrior::Yank            => Some(Command::Yank),
             CommandPrior::Quit(_)         => Some(Command::Quit),
+            CommandPrior::Recover         => Some(Command::Recover),
+            CommandPrior::CreateDir       => Some(Command::CreateDir),
+            CommandPrior::DiskChange      => Some(Command::DiskChange),
+            CommandPrior::PickBuffer      => Some(Command::PickBuffer),
+            CommandPrior::CloseBuffer     => Some(Command::CloseBuffer),
             CommandPrior::Change          => Some(Command::Change),
             CommandPrior::ReplaceChar     => Some(Command::ReplaceChar),
+            CommandPrior::SelectRegister  => Some(Command::SelectRegister),
+            CommandPrior::TextObject(operator, inner) => Some(Command::TextObject(operator, inner)),
             CommandPrior::Delete          => Some(Command::Delete(false)),
             CommandPrior::Search(ref pat) => Some(Command::Search(Some(pat.to_owned()))),
+            CommandPrior::OpenFile(ref path) => Some(Command::OpenFile(Some(path.to_owned()))),
+            CommandPrior::SaveAs(ref path) => Some(Command::SaveAs(Some(path.to_owned()))),
+            CommandPrior::Overwrite(ref path) => Some(Command::Overwrite(path.to_owned())),
+            CommandPrior::SetEncoding(ref label) => Some(
+                Command::SetEncoding(Some(label.to_owned()))
+            ),
 
             CommandPrior::ConfirmError    => panic!("Unknow error!"),
         };
@@ -87,3 +164,25 @@ pub fn handle_input(app: &mut App, key: KeyCode, rt: &Runtime) -> AppResult<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_digit_test() {
+        let mut app = App::new();
+
+        // `0` is a command without other digits before it
+        assert!(!count_digit(&mut app, KeyCode::Char('0')));
+        assert!(count_digit(&mut app, KeyCode::Char('1')));
+        assert!(count_digit(&mut app, KeyCode::Char('0')));
+        assert!(!count_digit(&mut app, KeyCode::Char('j')));
+        assert_eq!(app.count, Some(10));
+
+        for _ in 0..10 {
+            count_digit(&mut app, KeyCode::Char('9'));
+        }
+        assert_eq!(app.count, Some(MAX_COUNT));
+    }
+}
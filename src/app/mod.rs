@@ -1,8 +1,13 @@
 // App
 
+mod args;
+mod buffer;
+mod clipboard;
 mod handle_input;
+mod register;
 mod search;
 
+use std::path::Path;
 use std::sync::Arc;
 
 use tokio::sync::Mutex;
@@ -12,10 +17,16 @@ use crate::{
     config::{init_config, AppOption, Keymap},
     error::{AppError, AppResult, ErrorType},
     command::{Command, CommandPrior},
-    ui::{CommandEdit, EditorState},
-    fs::FileState,
+    ui::{CommandEdit, EditorState, ModalType},
+    fs::{FileState, PositionStore, SwapState},
 };
 
+use buffer::buffer_position;
+
+pub use args::AppArgs;
+pub use buffer::{Buffer, BufferList, BUFFER_KEYS};
+pub use clipboard::Clipboard;
+pub use register::{Register, Registers};
 pub use search::SearchIndicates;
 pub use handle_input::handle_input;
 
@@ -24,6 +35,8 @@ pub struct App {
     keymap: Keymap,
     options: AppOption,
     search_result: Arc<Mutex<SearchIndicates>>,
+    buffers: BufferList,
+    positions: PositionStore,
 
     pub file_state: FileState,
 
@@ -31,6 +44,12 @@ pub struct App {
 
     pub prior_command: CommandPrior,
 
+    // The count typed before a command.
+    pub count: Option<usize>,
+
+    pub registers: Registers,
+    pub clipboard: Clipboard,
+
     pub app_errors: AppError,
 
     pub ask_msg: Option<String>,
@@ -48,8 +67,13 @@ impl App {
             editor_state: EditorState::default(),
             app_errors: AppError::default(),
             prior_command: CommandPrior::None,
+            count: None,
+            registers: Registers::default(),
+            clipboard: Clipboard::default(),
             ask_msg: None,
-            update_stylized: true,
+            update_stylized: false,
+            buffers: BufferList::default(),
+            positions: PositionStore::default(),
             command_edit: CommandEdit::None,
             search_result: Arc::new(Mutex::new(
                 SearchIndicates::default()
@@ -86,10 +110,231 @@ impl App {
         &self.search_result
     }
 
+    /// Ask what to do with the file changed by others on disk.
+    pub fn ask_disk_change(&mut self) {
+        self.prior_command = CommandPrior::DiskChange;
+        self.ask_msg = Some(String::from("File has been changed on disk"));
+    }
+
+    /// Check whether the file is changed by others, which is only done when there's
+    /// no other command or message waiting for user.
+    pub async fn check_disk(&mut self) -> AppResult<()> {
+        if self.prior_command != CommandPrior::None ||
+            self.command_edit != CommandEdit::None ||
+            self.editor_state.modal.modal() != ModalType::Normal ||
+            !self.app_errors.empty()
+        {
+            return Ok(())
+        }
+
+        if self.file_state.notice_disk_change().await? {
+            self.ask_disk_change();
+        }
+
+        Ok(())
+    }
+
+    /// Check the swap file of active buffer, which is done once for each buffer.
+    async fn check_swap(&mut self) -> AppResult<()> {
+        match self.file_state.check_swap().await? {
+            SwapState::None => (),
+            SwapState::InUse(pid) => self.app_errors.append_errors(std::iter::once(
+                ErrorType::Specific(
+                    format!("This file is being edited by another tetor (pid {})", pid)
+                )
+            )),
+            SwapState::Recoverable => {
+                self.prior_command = CommandPrior::Recover;
+                self.ask_msg = Some(String::from("Found unsaved changes in swap file"));
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Replace the active buffer with `buffer`, return the previous one.
+    fn replace_buffer(&mut self, buffer: Buffer) -> Buffer {
+        Buffer {
+            file_state: std::mem::replace(&mut self.file_state, buffer.file_state),
+            editor_state: std::mem::replace(&mut self.editor_state, buffer.editor_state),
+            search_result: std::mem::replace(&mut self.search_result, buffer.search_result)
+        }
+    }
+
+    pub fn buffers(&self) -> &BufferList {
+        &self.buffers
+    }
+
+    /// Get the index of the buffer bound to `path`.
+    pub fn buffer_index(&self, path: &Path) -> Option<usize> {
+        self.buffers.slots()
+            .map(|slot| slot.map_or(&self.file_state, |buffer| &buffer.file_state))
+            .position(|file_state| file_state.path() == Some(path))
+    }
+
+    /// Make the buffer at `idx` active.
+    /// Its stylized content is kept while inactive, thus it's not updated here.
+    pub async fn switch_buffer(&mut self, idx: usize) -> AppResult<()> {
+        if idx == self.buffers.active() || idx >= self.buffers.len() {
+            return Ok(())
+        }
+
+        let buffer = self.buffers.take(idx);
+        let previous = self.replace_buffer(buffer);
+        self.buffers.set_active(idx, previous);
+
+        self.check_swap().await
+    }
+
+    /// Open the file at `path` as a new buffer, and make it active.
+    /// The buffer of the file is activated when it has been opened.
+    pub async fn open_buffer(&mut self, path: String) -> AppResult<()> {
+        if path == "-" {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Cannot read from stdin after starting")
+                ).pack()
+            )
+        }
+
+        if let Some(idx) = self.buffer_index(&FileState::get_absolute(path.to_owned())) {
+            return self.switch_buffer(idx).await
+        }
+
+        let mut buffer = Buffer::open(path, &AppArgs::default()).await?;
+        self.restore_position(&mut buffer).await;
+        self.buffers.push(buffer);
+
+        self.switch_buffer(self.buffers.len() - 1).await
+    }
+
+    /// Close the active buffer, return false when it's the last buffer.
+    pub async fn close_buffer(&mut self) -> bool {
+        if self.buffers.len() == 1 {
+            return false
+        }
+
+        self.record_position().await;
+        self.file_state.remove_swap().await;
+
+        let buffer = self.buffers.remove_active();
+        self.replace_buffer(buffer);
+
+        true
+    }
+
+    /// Get the count of buffers which have not been saved.
+    pub async fn unsaved_buffers(&self) -> usize {
+        let mut count = 0;
+
+        for slot in self.buffers.slots() {
+            let file_state = slot.map_or(&self.file_state, |buffer| &buffer.file_state);
+            count += file_state.not_save().await as usize;
+        }
+
+        count
+    }
+
+    /// Remove the swap files of all buffers when exiting.
+    pub async fn remove_swaps(&mut self) {
+        for slot in self.buffers.slots_mut() {
+            let file_state = slot.map_or(&mut self.file_state, |buffer| &mut buffer.file_state);
+            file_state.remove_swap().await;
+        }
+    }
+
+    /// Take the saved content of all buffers in order, which is written into stdout.
+    pub fn take_outputs(&mut self) -> Vec<Vec<u8>> {
+        let mut outputs = Vec::new();
+
+        for slot in self.buffers.slots_mut() {
+            let file_state = slot.map_or(&mut self.file_state, |buffer| &mut buffer.file_state);
+            outputs.extend(file_state.take_output());
+        }
+
+        outputs
+    }
+
+    /// Move the cursor of `buffer` to the position where its file was left.
+    async fn restore_position(&self, buffer: &mut Buffer) {
+        if !self.options.remember_position {
+            return
+        }
+
+        if let Some(position) = buffer.file_state.path().and_then(|path| self.positions.get(path)) {
+            buffer.restore(position).await;
+        }
+    }
+
+    /// Remember the position of active buffer.
+    async fn record_position(&mut self) {
+        if !self.options.remember_position {
+            return
+        }
+
+        let position = buffer_position(
+            &self.file_state,
+            &self.editor_state,
+            &self.search_result
+        ).await;
+
+        if let Some((path, position)) = position {
+            self.positions.record(path, position);
+        }
+    }
+
+    /// Remember the positions of all buffers when exiting.
+    pub async fn save_positions(&mut self) {
+        if !self.options.remember_position {
+            return
+        }
+
+        for slot in self.buffers.slots() {
+            let position = match slot {
+                Some(buffer) => buffer_position(
+                    &buffer.file_state,
+                    &buffer.editor_state,
+                    &buffer.search_result
+                ).await,
+                None => buffer_position(
+                    &self.file_state,
+                    &self.editor_state,
+                    &self.search_result
+                ).await,
+            };
+
+            if let Some((path, position)) = position {
+                self.positions.record(path, position);
+            }
+        }
+
+        // Failing to remember the positions shouldn't stop exiting.
+        let _ = self.positions.save(self.options.max_positions).await;
+    }
+
+    /// Get the names of all buffers in order, with whether they have been modified.
+    pub async fn buffer_names(&self) -> Vec<(String, bool)> {
+        let mut names = Vec::new();
+
+        for slot in self.buffers.slots() {
+            let file_state = slot.map_or(&self.file_state, |buffer| &buffer.file_state);
+            names.push((file_state.name(), file_state.not_save().await));
+        }
+
+        names
+    }
+
     // Initialization part starts from here
-    pub async fn init_app(&mut self, path: String) -> AppResult<()> {
-        let (file_result, keymap_result) = tokio::join!(
-            self.file_state.init(path),
+    pub async fn init_app(&mut self, args: AppArgs) -> AppResult<()> {
+        let (buffers_result, keymap_result) = tokio::join!(
+            async {
+                let mut buffers = Vec::new();
+                for path in args.paths.iter() {
+                    buffers.push(Buffer::open(path.to_owned(), &args).await?);
+                }
+
+                Ok::<_, AppError>(buffers)
+            },
             // self.file_state.init("/home/spring/test.el"),
             // self.file_state.init("/home/spring/Rust/hire/src/ui.rs"),
             // self.file_state.init("/var/log/pacman.log"),
@@ -97,12 +342,26 @@ impl App {
             init_config(&mut self.keymap, &mut self.options)
         );
 
-        (file_result?, keymap_result?);
+        let (mut buffers, _) = (buffers_result?, keymap_result?);
 
-        self.editor_state.update_linenr(
-            self.file_state.content_ref().lock().await.len()
-        );
+        if self.options.remember_position {
+            self.positions.load().await;
 
-        Ok(())
+            for buffer in buffers.iter_mut() {
+                self.restore_position(buffer).await;
+            }
+        }
+
+        let mut buffers = buffers.into_iter();
+
+        if let Some(buffer) = buffers.next() {
+            self.replace_buffer(buffer);
+        }
+
+        for buffer in buffers {
+            self.buffers.push(buffer);
+        }
+
+        self.check_swap().await
     }
 }
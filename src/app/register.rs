@@ -0,0 +1,144 @@
+// Registers
+
+use std::collections::{HashMap, VecDeque};
+
+/// The count of recent deletions kept in kill ring, which are named from '1' to '9'.
+const KILL_RING_SIZE: usize = 9;
+
+/// The text stored for pasting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    pub text: String,
+    pub linewise: bool          // Whether the text is made up of whole lines
+}
+
+/// The registers shared by all buffers.
+/// The unnamed register '"' always holds the latest yanked or deleted text,
+/// register '0' holds the latest yanked text, and the named registers 'a' to 'z'
+/// are only written when selected, whose uppercase names append to them.
+#[derive(Debug, Default)]
+pub struct Registers {
+    unnamed: Option<Register>,
+    yanked: Option<Register>,
+    named: HashMap<char, Register>,
+    kill_ring: VecDeque<Register>,
+
+    // The register selected for the next yanking, deleting or pasting.
+    selected: Option<char>
+}
+
+impl Register {
+    pub fn new(text: String, linewise: bool) -> Self {
+        Self { text, linewise }
+    }
+
+    /// Append `other` to the register, which becomes linewise if either of them is.
+    fn append(&mut self, other: Register) {
+        self.linewise |= other.linewise;
+
+        if self.linewise && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+        self.text.push_str(&other.text);
+
+        if self.linewise && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+}
+
+impl Registers {
+    pub fn is_valid(name: char) -> bool {
+        name == '"' || name.is_ascii_alphanumeric()
+    }
+
+    /// Select the register used by the next command, return false when `name` is invalid.
+    pub fn select(&mut self, name: char) -> bool {
+        if !Self::is_valid(name) {
+            return false
+        }
+
+        self.selected = Some(name);
+        true
+    }
+
+    pub fn selected(&self) -> Option<char> {
+        self.selected
+    }
+
+    pub fn unselect(&mut self) {
+        self.selected = None;
+    }
+
+    pub fn yank(&mut self, register: Register) {
+        self.yanked = Some(register.clone());
+        self.store(register);
+    }
+
+    /// Store the deleted text, which is pushed into kill ring as well.
+    pub fn kill(&mut self, register: Register) {
+        self.kill_ring.push_front(register.clone());
+        self.kill_ring.truncate(KILL_RING_SIZE);
+        self.store(register);
+    }
+
+    /// Get the register to be pasted, which is the selected one or the unnamed one.
+    pub fn get(&mut self) -> Option<&Register> {
+        match self.selected.take() {
+            None | Some('"') => self.unnamed.as_ref(),
+            Some('0') => self.yanked.as_ref(),
+            Some(name @ '1'..='9') => self.kill_ring.get(name as usize - '1' as usize),
+            Some(name) => self.named.get(&name.to_ascii_lowercase()),
+        }
+    }
+
+    /// Write the text into unnamed register & the selected named register.
+    fn store(&mut self, register: Register) {
+        let selected = self.selected.take();
+
+        if let Some(name) = selected.filter(char::is_ascii_alphabetic) {
+            let lowercase = name.to_ascii_lowercase();
+
+            match self.named.get_mut(&lowercase) {
+                Some(named) if name.is_ascii_uppercase() => named.append(register.clone()),
+                _ => {
+                    self.named.insert(lowercase, register.clone());
+                },
+            }
+        }
+
+        self.unnamed = Some(register);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_test() {
+        let mut registers = Registers::default();
+
+        registers.yank(Register::new(String::from("foo"), false));
+        assert!(registers.select('a'));
+        registers.kill(Register::new(String::from("bar\n"), true));
+        assert!(registers.select('A'));
+        registers.yank(Register::new(String::from("baz"), false));
+        registers.kill(Register::new(String::from("qux"), false));
+
+        assert!(!registers.select('-'));
+        assert_eq!(registers.get(), Some(&Register::new(String::from("qux"), false)));
+
+        registers.select('0');
+        assert_eq!(registers.get(), Some(&Register::new(String::from("baz"), false)));
+
+        registers.select('2');
+        assert_eq!(registers.get(), Some(&Register::new(String::from("bar\n"), true)));
+
+        registers.select('a');
+        assert_eq!(registers.get(), Some(&Register::new(String::from("bar\nbaz\n"), true)));
+
+        registers.select('b');
+        assert_eq!(registers.get(), None);
+    }
+}
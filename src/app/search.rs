@@ -2,10 +2,12 @@
 
 use std::ops::Range;
 
+use crate::utils::byte_to_grapheme;
+
 #[derive(Debug, Default)]
 pub struct SearchIndicates {
     target_str: String,
-    indicates: Vec<(Range<u16>, u16)>,
+    indicates: Vec<(Range<usize>, usize)>,
 
     selected: Option<usize>,
 }
@@ -28,6 +30,10 @@ impl SearchIndicates {
         true
     }
 
+    pub fn target(&self) -> &str {
+        &self.target_str
+    }
+
     pub fn selected(&self) -> Option<usize> {
         self.selected
     }
@@ -36,11 +42,11 @@ impl SearchIndicates {
         &mut self.selected
     }
 
-    pub fn indicates(&self) -> &Vec<(Range<u16>, u16)> {
+    pub fn indicates(&self) -> &Vec<(Range<usize>, usize)> {
         &self.indicates
     }
 
-    pub fn indicates_find(&self, cursor: (u16, u16)) -> bool {
+    pub fn indicates_find(&self, cursor: (usize, usize)) -> bool {
         for (x_range, y) in self.indicates.iter() {
             if cursor.1 == *y && x_range.contains(&cursor.0) {
                 return true
@@ -50,7 +56,7 @@ impl SearchIndicates {
         false
     }
 
-    pub fn current_indicate(&self) -> Option<(u16, u16)> {
+    pub fn current_indicate(&self) -> Option<(usize, usize)> {
         if self.selected.is_none() {
             return None
         }
@@ -60,7 +66,7 @@ impl SearchIndicates {
     }
 
     /// Select the nearest next item.
-    pub fn nearest_next(&mut self, cursor: (u16, u16)) -> Option<(u16, u16)> {
+    pub fn nearest_next(&mut self, cursor: (usize, usize)) -> Option<(usize, usize)> {
         if self.indicates.is_empty() {
             return None
         }
@@ -81,17 +87,34 @@ impl SearchIndicates {
         self.current_indicate()
     }
 
+    /// Set the search results, each of which is the range of grapheme columns
+    /// with its line number.
+    /// Find the items matching `pat` in `lines`.
+    pub fn find<I, S>(lines: I, pat: &str) -> Vec<(Range<usize>, usize)>
+    where I: Iterator<Item = S>, S: AsRef<str>
+    {
+        let mut indicates = Vec::new();
+
+        for (line_nr, line) in lines.enumerate() {
+            let line = line.as_ref();
+
+            for (idx, _) in line.match_indices(pat) {
+                let start = byte_to_grapheme(line, idx, false);
+                let end = byte_to_grapheme(line, idx + pat.len(), true);
+
+                indicates.push((start..end, line_nr));
+            }
+        }
+
+        indicates
+    }
+
     pub fn set<I>(&mut self, target: String, iter: I)
-    where I: Iterator<Item = (u16, u16)>
+    where I: Iterator<Item = (Range<usize>, usize)>
     {
         self.target_str = target;
         self.selected = None;
 
-        let target_len = self.target_str.len() as u16;
-        let to_raw = iter.map(|indicate| {
-            ((indicate.0 .. indicate.0 + target_len), indicate.1)
-        }).collect::<Vec<_>>();
-
-        self.indicates.extend(to_raw.into_iter());
+        self.indicates.extend(iter);
     }
 }
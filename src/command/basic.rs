@@ -1,15 +1,26 @@
 // Basic
 
+use std::path::PathBuf;
+
+use tokio::fs;
 use ratatui::crossterm::event::KeyCode;
 
 use crate::{
-    app::App,
+    app::{App, Register, SearchIndicates},
     error::{AppResult, ErrorType},
+    fs::{FileEncoding, FileState},
     ui::CommandEdit,
-    utils::cursor_compare_swap
+    utils::{cursor_compare_swap, grapheme_len}
 };
 
-use super::{command_type::CursorMoveType, CommandPrior};
+use super::{
+    command_type::CursorMoveType,
+    indent::{dedent_closer, newline_indent, split_line},
+    text_object::ObjectOperator,
+    word::word_spans,
+    Command,
+    CommandPrior
+};
 
 pub async fn change_insert(
     app: &mut App,
@@ -26,7 +37,7 @@ pub async fn change_insert(
             let cursor_after = match cursor_move {
                 CursorMoveType::Beg => start,
                 CursorMoveType::End => end,
-                CursorMoveType::Num(_) => {
+                _ => {
                     *app.editor_state.mark_mut() = None;
                     break;
                 },
@@ -54,16 +65,21 @@ pub async fn move_cursor(
     cursor_move: CursorMoveType
 ) -> AppResult<bool>
 {
-    *app.editor_state.cursor_mut() = cursor_move.after_move(
+    let tab_width = app.options().tab_width;
+
+    *app.editor_state.cursor_mut() = cursor_move.after_count_move(
         within_line,
         app.editor_state.cursor(),
-        &mut app.file_state
+        &mut app.file_state,
+        tab_width,
+        app.count
     ).await?;
 
     Ok(false)
 }
 
 pub async fn page_scroll(app: &mut App, scroll: isize) -> bool {
+    let scroll = scroll * app.count.unwrap_or(1) as isize;
     let editor_state = &mut app.editor_state;
     let scroll_after = (editor_state.offset() as isize) + (scroll * editor_state.height());
 
@@ -77,7 +93,10 @@ pub async fn page_scroll(app: &mut App, scroll: isize) -> bool {
 
     let scroll_after = scroll_after as usize;
 
-    let file_length = app.file_state.content_ref().lock().await.len() as isize;
+    let file_length = match app.file_state.hex() {
+        Some(hex) => hex.rows(),
+        None => app.file_state.content_ref().lock().await.len(),
+    } as isize;
     let max_offset = file_length - editor_state.height();
 
     if max_offset < 0 {
@@ -96,57 +115,43 @@ pub async fn page_scroll(app: &mut App, scroll: isize) -> bool {
 }
 
 pub async fn insert_char(app: &mut App, key: char) -> AppResult<bool> {
-    let mut use_space_tab = false;
-    let cursor_pos = app.editor_state.cursor();
-    let mut edit_line = app.file_state.get_lines(
-        cursor_pos.1,
-        cursor_pos.1
-    ).await?;
+    let mut cursor_pos = app.editor_state.cursor();
 
-    // Handle tab insert
-    if key == '\t' && !app.options().tab_indent {
-        use_space_tab = true;
-        edit_line[0].insert_str(cursor_pos.0 as usize, "    ");
-    } else if key == '\n' {
-        let temp_line = edit_line[0][cursor_pos.0 as usize..].to_owned();
-        edit_line[0].replace_range((cursor_pos.0 as usize).., "\n");
-        edit_line.push(temp_line);
-    } else {
-        edit_line[0].insert(cursor_pos.0 as usize, key);
-    }
+    let text = match key {
+        // Handle tab insert, the spaces fill up to the next tab stop.
+        '\t' if !app.options().tab_indent => {
+            let tab_width = app.options().tab_width;
+            let display_x = app.file_state.display_range(cursor_pos, tab_width).await.start;
 
-    app.file_state.modify_lines(
-        cursor_pos.1,
-        cursor_pos.1,
-        edit_line
-    ).await?;
+            " ".repeat(tab_width - display_x % tab_width)
+        },
 
-    if use_space_tab {
-        app.editor_state.cursor_mut().0 += 4;
-        return Ok(true)
-    }
+        '\n' if app.options().auto_indent => return split_line(app).await,
 
-    if key == '\n' {
-        let cursor = app.editor_state.cursor_mut();
-        cursor.0 = 0;
-        cursor.1 += 1;
-        return Ok(true)
-    }
+        ')' | ']' | '}' => {
+            cursor_pos = dedent_closer(app).await?;
+            key.to_string()
+        },
 
-    app.editor_state.cursor_mut().0 += 1;
+        _ => key.to_string()
+    };
+
+    *app.editor_state.cursor_mut() = app.file_state
+        .insert_text(cursor_pos, &text)
+        .await?;
 
     Ok(true)
 }
 
 pub async fn delete_char(app: &mut App) -> AppResult<bool> {
     let cursor = app.editor_state.cursor();
-    let mut current_line = app.file_state
+    let current_line = app.file_state
         .get_lines(cursor.1, cursor.1)
         .await?;
 
     // Delete current line
     if current_line.len() == 1 && current_line[0] == "\n" {
-        let file_length = (app.file_state.content_ref().lock().await.len() - 1) as u16;
+        let file_length = app.file_state.content_ref().lock().await.len() - 1;
         app.file_state.modify_lines(cursor.1, cursor.1, Vec::new()).await?;
 
         if cursor.1 >= file_length {
@@ -157,10 +162,15 @@ pub async fn delete_char(app: &mut App) -> AppResult<bool> {
         return Ok(true)
     }
 
-    current_line[0].remove(cursor.0 as usize);
+    // The line break is deleted alone, and counted chars stop before it.
+    let line_end = grapheme_len(&current_line[0]) - 1;
+    let end = if cursor.0 < line_end {
+        (cursor.0 + app.count.unwrap_or(1)).min(line_end)
+    } else {
+        cursor.0 + 1
+    };
 
-    app.file_state.modify_lines(cursor.1, cursor.1, current_line).await?;
-    app.file_state.file_modify().await;
+    app.file_state.remove_text(cursor, (end, cursor.1)).await?;
 
     Ok(true)
 }
@@ -176,22 +186,20 @@ pub async fn replace_char(app: &mut App, key: Option<KeyCode>) -> AppResult<bool
 
     if let KeyCode::Char(_key) = key.unwrap() {
         let cursor = app.editor_state.cursor();
-        let mut line = app.file_state
-            .get_lines(cursor.1, cursor.1)
-            .await?[0]
-            .to_owned()
-            .into_bytes();
-
-        line[cursor.0 as usize] = _key as u8;
-
-        app.file_state.modify_lines(
-            cursor.1,
-            cursor.1,
-            vec![
-                String::from_utf8(line)
-                    .expect("Error code 2 at replace_char in basic.rs.")
-            ]
+        let count = app.count.unwrap_or(1);
+
+        // Like Vim, nothing is replaced when there're not enough chars.
+        let line_length = grapheme_len(&app.file_state.get_lines(cursor.1, cursor.1).await?[0]);
+        if count > 1 && cursor.0 + count >= line_length {
+            return Ok(false)
+        }
+
+        app.file_state.replace_text(
+            cursor,
+            (cursor.0 + count, cursor.1),
+            &_key.to_string().repeat(count)
         ).await?;
+        app.editor_state.cursor_mut().0 = cursor.0 + count - 1;
     }
 
     Ok(true)
@@ -204,33 +212,17 @@ pub async fn delete(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
     if key.is_none() {
         match state.mark() {
             Some(mark_pos) => {
-                if mark_pos == cursor {
-                    *state.mark_mut() = None;
-                    return Ok(false)
-                }
+                *state.mark_mut() = None;
 
                 // Delete marked region
-                let (mut start, mut end) = (mark_pos, cursor);
-                cursor_compare_swap(&mut start, &mut end);
-
-                let origin_lines = app.file_state.get_lines(start.1, end.1).await?;
-                let mut new_line = String::new();
-                // The true end position of the marked region equals to cursor_x - 1
-                if end == mark_pos {
-                    end.0 += 1;
-                }
-
-                new_line.push_str(&origin_lines[0][0..(start.0 as usize)]);
-                new_line.push_str(
-                    &origin_lines
-                        .last()
-                        .expect("Error code 1 at delete in basic.rs!")
-                        [(end.0 as usize)..]
-                );
+                let (start, end) = match marked_region(mark_pos, cursor) {
+                    Some(region) => region,
+                    None => return Ok(false),
+                };
 
-                app.file_state.modify_lines(start.1, end.1, vec![new_line]).await?;
+                let removed = app.file_state.remove_text(start, end).await?;
+                app.registers.kill(Register::new(removed, false));
                 *state.cursor_mut() = start;
-                *state.mark_mut() = None;
 
                 return Ok(true)
             },
@@ -244,22 +236,42 @@ pub async fn delete(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
     // NOTE: Avoid the occurred error makes this value cannot be reset.
     app.prior_command = CommandPrior::None;
 
+    if let Some(inner) = object_scope(key.unwrap()) {
+        app.prior_command = CommandPrior::TextObject(ObjectOperator::Delete, inner);
+        return Ok(false)
+    }
+
+    if let Some(cursor_move) = word_motion(app, key.unwrap()) {
+        return delete_word(app, cursor_move, false).await
+    }
+
+    let file_length = app.file_state.content_ref().lock().await.len();
+
+    if file_length == 0 {
+        return Ok(false)
+    }
+
+    let last_line = (cursor.1 + app.count.unwrap_or(1) - 1).min(file_length - 1);
+    let lines = app.file_state.get_lines(cursor.1, last_line).await?.concat();
+
     match key.unwrap() {
         KeyCode::Char('d') => {
-            app.file_state.modify_lines(cursor.1, cursor.1, Vec::new()).await?;
+            app.file_state.modify_lines(cursor.1, last_line, Vec::new()).await?;
+            app.registers.kill(Register::new(lines, true));
 
             let file_length = app.file_state.content_ref().lock().await.len();
 
-            if cursor.1 > file_length as u16 {
-                state.cursor_mut().1 -= 1;
+            if cursor.1 >= file_length {
+                app.editor_state.cursor_mut().1 = file_length.saturating_sub(1);
             }
         },
         KeyCode::Tab => {
             app.file_state.modify_lines(
                 cursor.1,
-                cursor.1,
-                vec![String::from("\n")]
+                last_line,
+                vec![String::from("\n"); last_line - cursor.1 + 1]
             ).await?;
+            app.registers.kill(Register::new(lines, true));
         },
         _ => return Err(
             ErrorType::Specific(
@@ -268,11 +280,122 @@ pub async fn delete(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
         )
     }
 
-    state.cursor_mut().0 = 0;
+    app.editor_state.cursor_mut().0 = 0;
 
     Ok(true)
 }
 
+/// Get whether `key` starts an inner or an around text object.
+fn object_scope(key: KeyCode) -> Option<bool> {
+    match key {
+        KeyCode::Char('i') => Some(true),
+        KeyCode::Char('a') => Some(false),
+        _ => None
+    }
+}
+
+/// Get the word motion bound to `key`, which can be the target of deleting & changing.
+fn word_motion(app: &App, key: KeyCode) -> Option<CursorMoveType> {
+    match app.get_command(key)? {
+        Command::Move(_, cursor_move @ (
+            CursorMoveType::WordForward(_) |
+            CursorMoveType::WordBackward(_) |
+            CursorMoveType::WordEnd(_)
+        )) => Some(cursor_move),
+        _ => None
+    }
+}
+
+/// Delete the text between cursor & the target of word motion, the end of word is inclusive.
+/// Like Vim, the whitespaces after the word are kept when changing it, and deleting words
+/// forward stops at the end of line.
+async fn delete_word(app: &mut App, cursor_move: CursorMoveType, change: bool) -> AppResult<bool> {
+    let cursor = app.editor_state.cursor();
+    let tab_width = app.options().tab_width;
+    let count = app.count.unwrap_or(1);
+    let target = cursor_move.after_count_move(
+        false,
+        cursor,
+        &mut app.file_state,
+        tab_width,
+        app.count
+    ).await?;
+
+    let line = app.file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+
+    let (start, end) = match cursor_move {
+        CursorMoveType::WordForward(bigword) => {
+            let current = word_spans(&line, bigword).into_iter()
+                .find(|span| span.contains(&cursor.0));
+
+            match current {
+                Some(span) if change => {
+                    // The counted words after current one are changed to their ends.
+                    let end = CursorMoveType::WordEnd(bigword).after_count_move(
+                        false,
+                        (span.end - 1, cursor.1),
+                        &mut app.file_state,
+                        tab_width,
+                        Some(count - 1)
+                    ).await?;
+
+                    (cursor, (end.0 + 1, end.1))
+                },
+                _ => {
+                    // The last word moved over decides whether to stop at its line end.
+                    let last = cursor_move.after_count_move(
+                        false,
+                        cursor,
+                        &mut app.file_state,
+                        tab_width,
+                        Some(count - 1)
+                    ).await?;
+                    let last_end = grapheme_len(&app.file_state.get_lines(last.1, last.1).await?[0]) - 1;
+
+                    if target.1 > last.1 && last.0 < last_end {
+                        (cursor, (last_end, last.1))
+                    } else {
+                        (cursor, target)
+                    }
+                },
+            }
+        },
+        CursorMoveType::WordEnd(_) if target != cursor => (cursor, (target.0 + 1, target.1)),
+        _ => (target, cursor),
+    };
+
+    if start == end {
+        return Ok(false)
+    }
+
+    let removed = app.file_state.remove_text(start, end).await?;
+    app.registers.kill(Register::new(removed, false));
+    *app.editor_state.cursor_mut() = start;
+
+    Ok(true)
+}
+
+/// Get the start & the exclusive end of marked region, which is None when it's empty.
+pub(super) fn marked_region(
+    mark: (usize, usize),
+    cursor: (usize, usize)
+) -> Option<((usize, usize), (usize, usize))>
+{
+    if mark == cursor {
+        return None
+    }
+
+    let (mut start, mut end) = (mark, cursor);
+    cursor_compare_swap(&mut start, &mut end);
+
+    // The true end position of the marked region equals to cursor_x - 1
+    if end == mark {
+        end.0 += 1;
+    }
+
+    Some((start, end))
+}
+
 pub async fn change(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
     if key.is_none() {
         let to_update = delete(app, None).await?;
@@ -288,14 +411,22 @@ pub async fn change(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
 
     app.prior_command = CommandPrior::None;
 
+    if let Some(inner) = object_scope(key.unwrap()) {
+        app.prior_command = CommandPrior::TextObject(ObjectOperator::Change, inner);
+        return Ok(false)
+    }
+
     let to_update = match key.unwrap() {
         KeyCode::Char('c') => delete(app, Some(KeyCode::Char('d'))).await?,
         KeyCode::Tab => delete(app, Some(KeyCode::Tab)).await?,
-        _ => return Err(
-            ErrorType::Specific(
-                String::from("Invalid key command")
-            ).pack()
-        )
+        _key => match word_motion(app, _key) {
+            Some(cursor_move) => delete_word(app, cursor_move, true).await?,
+            None => return Err(
+                ErrorType::Specific(
+                    String::from("Invalid key command")
+                ).pack()
+            ),
+        }
     };
 
     app.editor_state.modal.switch_insert();
@@ -311,6 +442,12 @@ pub fn mark(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
     }
 
     app.prior_command = CommandPrior::None;
+
+    if let Some(inner) = object_scope(key.unwrap()) {
+        app.prior_command = CommandPrior::TextObject(ObjectOperator::Mark, inner);
+        return Ok(false)
+    }
+
     let state = &mut app.editor_state;
 
     match key.unwrap() {
@@ -332,39 +469,33 @@ pub fn mark(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
     Ok(false)
 }
 
-pub async fn newline(app: &mut App, down: bool) -> bool {
-    let mut file_content = app.file_state.content_ref().lock().await;
-    let cursor = app.editor_state.cursor();
-    let mut line_after = cursor.1 as usize;
-
-    let new_line = String::from("\n");
+pub async fn newline(app: &mut App, down: bool) -> AppResult<bool> {
+    let file_length = app.file_state.content_ref().lock().await.len();
 
     // NOTE: When the file is empty, newline will only create a line.
-    if file_content.is_empty() {
-        file_content.push(new_line);
-        return true
+    let count = app.count.unwrap_or(1);
+
+    if file_length == 0 {
+        app.file_state.insert_lines(0, vec![String::from("\n"); count]).await?;
+        return Ok(true)
     }
 
+    let indent = newline_indent(app, app.editor_state.cursor().1, down).await?;
+
+    let mut line_after = app.editor_state.cursor().1;
     if down {
         line_after += 1;
     }
 
-    app.file_state.file_modify().await;
-    *app.editor_state.cursor_mut() = (0, line_after as u16);
-
-    if line_after >= file_content.len() {
-        file_content.push(new_line);
-        drop(file_content);
-
-        app.get_modal().switch_insert();
-        return true
+    if line_after > file_length {
+        line_after = file_length;
     }
 
-    file_content.insert(line_after, new_line);
-    drop(file_content);
+    app.file_state.insert_lines(line_after, vec![format!("{}\n", indent); count]).await?;
+    *app.editor_state.cursor_mut() = (grapheme_len(&indent), line_after);
 
     app.get_modal().switch_insert();
-    true
+    Ok(true)
 }
 
 pub async fn backward_char(app: &mut App) -> AppResult<bool> {
@@ -375,29 +506,18 @@ pub async fn backward_char(app: &mut App) -> AppResult<bool> {
             return Ok(false)
         }
 
-        let mut lines = app.file_state.get_lines(cursor.1 - 1, cursor.1).await?;
-        *app.editor_state.cursor_mut() = (
-            lines[0].len() as u16 - 1,
-            cursor.1 - 1
+        let prev_length = grapheme_len(
+            &app.file_state.get_lines(cursor.1 - 1, cursor.1 - 1).await?[0]
         );
+        let line_end = (prev_length - 1, cursor.1 - 1);
 
-        lines[0].pop();
-        app.file_state.modify_lines(
-            cursor.1 - 1,
-            cursor.1,
-            vec![lines.join("")]
-        ).await?;
+        app.file_state.remove_text(line_end, cursor).await?;
+        *app.editor_state.cursor_mut() = line_end;
 
-        app.file_state.file_modify().await;
         return Ok(true)
     }
 
-    let mut modified_line = app.file_state.get_lines(cursor.1, cursor.1).await?;
-    
-    modified_line[0].remove(cursor.0 as usize - 1);
-
-    app.file_state.modify_lines(cursor.1, cursor.1, modified_line).await?;
-    app.file_state.file_modify().await;
+    app.file_state.remove_text((cursor.0 - 1, cursor.1), cursor).await?;
     app.editor_state.cursor_mut().0 -= 1;
 
     Ok(true)
@@ -419,16 +539,7 @@ pub async fn search(app: &mut App, pattern: Option<String>) -> AppResult<bool> {
     }
 
     let content = app.file_state.content_ref().lock().await;
-    let mut indicates: Vec<(u16, u16)> = Vec::new();
-
-    let mut line_nr = 0;
-    for line in content.iter() {
-        for indicate in line.match_indices(&pat) {
-            indicates.push((indicate.0 as u16, line_nr));
-        }
-
-        line_nr += 1;
-    }
+    let indicates = SearchIndicates::find(content.lines(), &pat);
 
     if indicates.is_empty() {
         app.prior_command = CommandPrior::None;
@@ -452,34 +563,36 @@ pub async fn search(app: &mut App, pattern: Option<String>) -> AppResult<bool> {
 
 pub async fn search_jump(app: &mut App, next: bool) -> AppResult<bool> {
     let mut search_ref = app.search_ref().lock().await;
-    let indicates = search_ref.indicates();
+    let indicates_len = search_ref.indicates().len();
 
-    if indicates.is_empty() {
+    if indicates_len == 0 {
         return Ok(false)
     }
 
     // Update current select index
     let move_way = if next { 1 } else { -1 };
 
-    *search_ref.selected_mut() = match search_ref.selected() {
-        None => {
-            if next {
-                Some(0)
-            } else {
-                Some(indicates.len() - 1)
-            }
-        },
-        Some(i) => {
-            let idx_after = i as isize + move_way;
-            if idx_after < 0 {
-                Some(indicates.len() - 1)
-            } else if idx_after as usize == indicates.len() {
-                Some(0)
-            } else {
-                Some(idx_after as usize)
+    for _ in 0..app.count.unwrap_or(1) {
+        *search_ref.selected_mut() = match search_ref.selected() {
+            None => {
+                if next {
+                    Some(0)
+                } else {
+                    Some(indicates_len - 1)
+                }
+            },
+            Some(i) => {
+                let idx_after = i as isize + move_way;
+                if idx_after < 0 {
+                    Some(indicates_len - 1)
+                } else if idx_after as usize == indicates_len {
+                    Some(0)
+                } else {
+                    Some(idx_after as usize)
+                }
             }
-        }
-    };
+        };
+    }
 
     // Move cursor
     let cursor = search_ref.current_indicate().unwrap();
@@ -492,6 +605,18 @@ pub async fn search_jump(app: &mut App, next: bool) -> AppResult<bool> {
 
 /// The general command binded for ESC key.
 pub async fn escape_command(app: &mut App) -> AppResult<bool> {
+    // Cancel count, which is cleared after the command
+    if app.count.is_some() {
+        return Ok(false)
+    }
+
+    // Cancel register selection
+    if app.registers.selected().is_some() {
+        app.registers.unselect();
+
+        return Ok(false)
+    }
+
     // Cancel mark
     if app.editor_state.mark().is_some() {
         *app.editor_state.mark_mut() = None;
@@ -508,20 +633,183 @@ pub async fn escape_command(app: &mut App) -> AppResult<bool> {
     Ok(false)
 }
 
+pub async fn undo(app: &mut App) -> AppResult<bool> {
+    match app.file_state.undo().await {
+        Some(cursor) => {
+            *app.editor_state.cursor_mut() = cursor;
+            *app.editor_state.mark_mut() = None;
+
+            Ok(true)
+        },
+        None => Err(
+            ErrorType::Specific(
+                String::from("Already at the oldest change")
+            ).pack()
+        )
+    }
+}
+
+pub async fn redo(app: &mut App) -> AppResult<bool> {
+    match app.file_state.redo().await {
+        Some(cursor) => {
+            *app.editor_state.cursor_mut() = cursor;
+            *app.editor_state.mark_mut() = None;
+
+            Ok(true)
+        },
+        None => Err(
+            ErrorType::Specific(
+                String::from("Already at the newest change")
+            ).pack()
+        )
+    }
+}
+
 pub async fn save(app: &mut App) -> AppResult<bool> {
-    app.file_state.save_content().await?;
+    // Ask before overwriting the changes made by others.
+    if app.file_state.disk_changed().await? {
+        app.ask_disk_change();
+        return Ok(false)
+    }
 
-    Ok(false)
+    let transformed = transform_content(app).await;
+
+    // The new file may be placed in a directory which doesn't exist.
+    if let Some(dir) = app.file_state.missing_dir().await {
+        app.prior_command = CommandPrior::CreateDir;
+        app.ask_msg = Some(format!("Directory {} doesn't exist, create it?", dir.display()));
+
+        return Ok(transformed)
+    }
+
+    let backup = app.options().backup;
+    app.file_state.save_content(backup).await?;
+
+    Ok(transformed)
+}
+
+/// Apply the on_save transformations for the file type, return whether the content is changed.
+async fn transform_content(app: &mut App) -> bool {
+    let transform = app.options().on_save.get(app.file_state.syntax_name().await.as_deref());
+    let (tab_indent, tab_width) = (app.options().tab_indent, app.options().tab_width);
+
+    if !app.file_state.transform_content(&transform, tab_indent, tab_width).await {
+        return false
+    }
+
+    // The cursor may be out of the transformed content.
+    let content = app.file_state.content_ref().lock().await;
+    let (col, line) = app.editor_state.cursor();
+    let line = line.min(content.len() - 1);
+    let col = col.min(grapheme_len(&content.line(line)) - 1);
+
+    app.editor_state.update_linenr(content.len());
+    drop(content);
+
+    *app.editor_state.cursor_mut() = (col, line);
+
+    // The transformation is committed before saving, thus its result is the saved state.
+    app.file_state.commit_step((col, line));
+    app.file_state.begin_step((col, line));
+
+    true
+}
+
+/// Save the content into the file at `path`, which the buffer is bound to afterwards.
+pub async fn save_as(app: &mut App, path: Option<String>) -> AppResult<bool> {
+    if path.is_none() {
+        let init = app.file_state.path()
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        app.command_edit = CommandEdit::new(init, CommandPrior::SaveAs(String::new()));
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+
+    let path = path.unwrap();
+    let absolute = FileState::get_absolute(path.to_owned());
+
+    if app.buffer_index(&absolute).is_some_and(|idx| idx != app.buffers().active()) {
+        return Err(
+            ErrorType::Specific(
+                format!("{} is opened in another buffer", absolute.display())
+            ).pack()
+        )
+    }
+
+    if app.file_state.path() != Some(&absolute) && fs::try_exists(&absolute).await? {
+        app.prior_command = CommandPrior::Overwrite(path);
+        app.ask_msg = Some(format!("File {} exists, overwrite it?", absolute.display()));
+
+        return Ok(false)
+    }
+
+    save_rebound(app, absolute).await
+}
+
+pub async fn overwrite(app: &mut App, path: String, key: Option<KeyCode>) -> AppResult<bool> {
+    app.prior_command = CommandPrior::None;
+    app.ask_msg = None;
+
+    if key != Some(KeyCode::Char('y')) {
+        return Ok(false)
+    }
+
+    save_rebound(app, FileState::get_absolute(path)).await
+}
+
+/// Save the buffer into another file, which is bound only when saved successfully.
+/// The previous file is kept while asking user before saving.
+async fn save_rebound(app: &mut App, path: PathBuf) -> AppResult<bool> {
+    app.file_state.rebind(path).await;
+
+    let result = save(app).await;
+
+    if result.is_err() ||
+        !matches!(app.prior_command, CommandPrior::CreateDir | CommandPrior::DiskChange)
+    {
+        app.file_state.restore_binding().await;
+    }
+
+    result?;
+
+    Ok(true)
+}
+
+pub async fn create_dir(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    app.prior_command = CommandPrior::None;
+    app.ask_msg = None;
+
+    let result = match key {
+        Some(KeyCode::Char('y')) => save_created(app).await,
+        _ => Ok(()),
+    };
+
+    // The file rebound by save-as is given up when it's not saved.
+    app.file_state.restore_binding().await;
+
+    result.map(|_| false)
+}
+
+async fn save_created(app: &mut App) -> AppResult<()> {
+    app.file_state.create_dir().await?;
+
+    let backup = app.options().backup;
+    app.file_state.save_content(backup).await
 }
 
 pub async fn quit(app: &mut App, key: Option<KeyCode>) -> bool {
     if key.is_none() {
-        if app.file_state.not_save().await {
-            app.prior_command = CommandPrior::Quit(false);
-            app.ask_msg = Some(String::from("File has not been saved, still quit?"));
-        } else {
-            app.prior_command = CommandPrior::Quit(true);
-        }
+        let unsaved = app.unsaved_buffers().await;
+
+        app.prior_command = CommandPrior::Quit(unsaved == 0);
+        app.ask_msg = match unsaved {
+            0 => None,
+            1 => Some(String::from("File has not been saved, still quit?")),
+            _ => Some(format!("{} buffers have not been saved, still quit?", unsaved)),
+        };
 
         return false
     }
@@ -537,3 +825,199 @@ pub async fn quit(app: &mut App, key: Option<KeyCode>) -> bool {
 
     false
 }
+
+pub async fn set_encoding(app: &mut App, label: Option<String>) -> AppResult<bool> {
+    if label.is_none() {
+        app.command_edit = CommandEdit::new(
+            app.file_state.encoding().name(),
+            CommandPrior::SetEncoding(String::new())
+        );
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+
+    let label = label.unwrap();
+    let encoding = match FileEncoding::from_label(&label) {
+        Some(encoding) => encoding,
+        None => return Err(
+            ErrorType::Specific(
+                format!("Unknown encoding: {}", label)
+            ).pack()
+        ),
+    };
+
+    app.file_state.set_encoding(encoding).await;
+
+    Ok(false)
+}
+
+pub async fn switch_line_ending(app: &mut App) -> AppResult<bool> {
+    app.file_state.switch_line_ending().await;
+
+    Ok(false)
+}
+
+pub async fn recover(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    app.prior_command = CommandPrior::None;
+    app.ask_msg = None;
+
+    let diff = match key {
+        Some(KeyCode::Char('r')) => false,
+        Some(KeyCode::Char('d')) => true,
+        _ => {
+            app.file_state.discard_swap();
+            return Ok(false)
+        }
+    };
+
+    check_writable(app)?;
+
+    let changed = app.file_state.recover_swap().await;
+    let content_len = app.file_state.content_ref().lock().await.len();
+    app.editor_state.update_linenr(content_len);
+
+    if !diff || changed.is_empty() {
+        return Ok(true)
+    }
+
+    highlight_lines(app, changed).await?;
+
+    Ok(false)
+}
+
+pub async fn reload(app: &mut App) -> AppResult<bool> {
+    app.file_state.reload().await?;
+    *app.editor_state.mark_mut() = None;
+
+    // Keep cursor within the new content
+    let content = app.file_state.content_ref().lock().await;
+    let mut cursor = app.editor_state.cursor();
+    cursor.1 = cursor.1.min(content.len().saturating_sub(1));
+
+    if !content.is_empty() {
+        cursor.0 = cursor.0.min(grapheme_len(&content.line(cursor.1)) - 1);
+    }
+
+    let content_len = content.len();
+    drop(content);
+
+    *app.editor_state.cursor_mut() = cursor;
+    app.editor_state.update_linenr(content_len);
+
+    Ok(true)
+}
+
+pub async fn disk_change(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    app.prior_command = CommandPrior::None;
+    app.ask_msg = None;
+
+    // The file rebound by save-as is given up unless it's overwritten.
+    if key != Some(KeyCode::Char('o')) {
+        app.file_state.restore_binding().await;
+    }
+
+    match key {
+        Some(KeyCode::Char('r')) => reload(app).await,
+        Some(KeyCode::Char('o')) => {
+            check_writable(app)?;
+
+            let backup = app.options().backup;
+            if let Err(err) = app.file_state.save_content(backup).await {
+                app.file_state.restore_binding().await;
+                return Err(err)
+            }
+
+            Ok(false)
+        },
+        Some(KeyCode::Char('d')) => {
+            let changed = app.file_state.diff_disk().await?;
+            highlight_lines(app, changed).await?;
+
+            Ok(false)
+        },
+        _ => Ok(false)
+    }
+}
+
+pub fn toggle_readonly(app: &mut App) -> bool {
+    let readonly = app.file_state.is_readonly();
+    app.file_state.set_readonly(!readonly);
+
+    false
+}
+
+/// Return error when the file is in read-only mode.
+pub fn check_writable(app: &App) -> AppResult<()> {
+    if app.file_state.is_readonly() {
+        return Err(
+            ErrorType::Specific(
+                String::from("Cannot run this command in read-only mode")
+            ).pack()
+        )
+    }
+
+    Ok(())
+}
+
+/// Highlight whole lines as search results, and move cursor to the first one.
+async fn highlight_lines(app: &mut App, lines: Vec<usize>) -> AppResult<()> {
+    if lines.is_empty() {
+        return Ok(())
+    }
+
+    // The search results would be cleared if stylized content is updated by frame.
+    app.file_state.refresh_stylized(
+        app.editor_state.offset(),
+        app.editor_state.height() as usize
+    ).await?;
+
+    let content = app.file_state.content_ref().lock().await;
+    let indicates = lines.into_iter()
+        .map(|line_nr| (0..grapheme_len(&content.line(line_nr)), line_nr))
+        .collect::<Vec<_>>();
+    drop(content);
+
+    let mut search_result = app.search_ref().lock().await;
+    search_result.clear();
+    search_result.set(String::new(), indicates.into_iter());
+
+    let cursor_after = search_result.nearest_next((0, 0)).unwrap();
+    drop(search_result);
+    *app.editor_state.cursor_mut() = cursor_after;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn save_as_test() {
+        let dir = TempDir::new("save_as_test");
+        let (path, new_path) = (dir.join("a.txt"), dir.join("missing/b.txt"));
+        std::fs::write(&path, "a\n").unwrap();
+
+        let mut app = App::new();
+        app.file_state.init(path.to_string_lossy().into_owned(), None).await.unwrap();
+
+        let save_as = Command::SaveAs(Some(new_path.to_string_lossy().into_owned()));
+        save_as.execute(&mut app, None).await.unwrap();
+        assert_eq!(app.prior_command, CommandPrior::CreateDir);
+        assert_eq!(app.file_state.path(), Some(new_path.as_path()));
+
+        // The previous file is bound again when the directory is not created.
+        Command::CreateDir.execute(&mut app, Some(KeyCode::Char('n'))).await.unwrap();
+        assert_eq!(app.file_state.path(), Some(path.as_path()));
+        assert!(!new_path.exists());
+
+        save_as.execute(&mut app, None).await.unwrap();
+        Command::CreateDir.execute(&mut app, Some(KeyCode::Char('y'))).await.unwrap();
+        assert_eq!(app.file_state.path(), Some(new_path.as_path()));
+        assert_eq!(std::fs::read_to_string(&new_path).unwrap(), "a\n");
+    }
+}
@@ -0,0 +1,89 @@
+// Buffer Commands
+
+use ratatui::crossterm::event::KeyCode;
+
+use crate::{
+    app::{App, BUFFER_KEYS},
+    error::AppResult,
+    ui::CommandEdit
+};
+
+use super::CommandPrior;
+
+/// Switch to the next or previous buffer, which wraps around the buffer list.
+pub async fn switch_buffer(app: &mut App, next: bool) -> AppResult<bool> {
+    let len = app.buffers().len();
+    let active = app.buffers().active();
+
+    let idx = if next {
+        (active + 1) % len
+    } else {
+        (active + len - 1) % len
+    };
+
+    app.switch_buffer(idx).await?;
+
+    Ok(false)
+}
+
+/// Pick the buffer with its key in BUFFER_KEYS.
+pub async fn pick_buffer(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    if key.is_none() {
+        app.prior_command = CommandPrior::PickBuffer;
+        app.ask_msg = Some(String::from("Pick a buffer"));
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+    app.ask_msg = None;
+
+    if let KeyCode::Char(_key) = key.unwrap() {
+        if let Some(idx) = BUFFER_KEYS.find(_key) {
+            app.switch_buffer(idx).await?;
+        }
+    }
+
+    Ok(false)
+}
+
+pub async fn open_file(app: &mut App, path: Option<String>) -> AppResult<bool> {
+    if path.is_none() {
+        app.command_edit = CommandEdit::new(
+            String::new(),
+            CommandPrior::OpenFile(String::new())
+        );
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+    app.open_buffer(path.unwrap()).await?;
+
+    Ok(false)
+}
+
+/// Close the active buffer, quit the app when it's the last one.
+pub async fn close_buffer(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    if key.is_none() {
+        if app.file_state.not_save().await {
+            app.prior_command = CommandPrior::CloseBuffer;
+            app.ask_msg = Some(String::from("Buffer has not been saved, still close?"));
+
+            return Ok(false)
+        }
+    } else {
+        app.prior_command = CommandPrior::None;
+        app.ask_msg = None;
+
+        if key != Some(KeyCode::Char('y')) {
+            return Ok(false)
+        }
+    }
+
+    if !app.close_buffer().await {
+        app.prior_command = CommandPrior::Quit(true);
+    }
+
+    Ok(false)
+}
@@ -2,15 +2,29 @@
 
 use ratatui::crossterm::event::KeyCode;
 
-use crate::{app::App, error::AppResult};
+use crate::{
+    app::App,
+    error::{AppResult, ErrorType},
+    utils::{display_to_grapheme, grapheme_len}
+};
 
 use super::basic::*;
+use super::buffer::*;
+use super::hex::*;
+use super::register::*;
+use super::text_object::{text_object, ObjectOperator};
+use super::word::{word_backward, word_end, word_forward};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CursorMoveType {
     Num(i16),
     Beg,
-    End
+    End,
+
+    // Word motions cross lines, whose value is whether it's a WORD delimited by whitespaces.
+    WordForward(bool),
+    WordBackward(bool),
+    WordEnd(bool)
 }
 
 /// The prior command to be executed.
@@ -19,12 +33,24 @@ pub enum CursorMoveType {
 pub enum CommandPrior {
     None,
     Mark,
+    Yank,
     Delete,
     Change,
     Quit(bool),
+    Recover,
+    CreateDir,
+    DiskChange,
+    PickBuffer,
+    CloseBuffer,
     ReplaceChar,
     ConfirmError,
-    Search(String)
+    SelectRegister,
+    TextObject(ObjectOperator, bool),
+    Search(String),
+    SaveAs(String),
+    OpenFile(String),
+    Overwrite(String),
+    SetEncoding(String)
 }
 
 #[derive(Debug, Clone)]
@@ -32,15 +58,38 @@ pub enum Command {
     Save,
     Quit,
     Mark,
+    Undo,
+    Redo,
+    Yank,
     Change,
+    Reload,
+    Recover,
+    CreateDir,
+    ToggleHex,
+    DiskChange,
+    PickBuffer,
+    CloseBuffer,
     ReplaceChar,
     BackwardChar,
+    ToggleReadonly,
     EscapeCommand,
+    CopyClipboard,
+    SelectRegister,
+    SwitchLineEnding,
 
+    Paste(bool),                // Whether paste after cursor
+    PasteClipboard(bool),       // Whether paste after cursor
+    TextObject(ObjectOperator, bool),   // Whether it's inner object
     Delete(bool),               // Whether delete char
     NewLine(bool),              // Whether open down a new line
     Search(Option<String>),
+    SaveAs(Option<String>),
+    Overwrite(String),
     SearchJump(bool),           // Whether jump to the next item
+    SwitchBuffer(bool),         // Whether switch to the next buffer
+    MoveNibble(isize),
+    OpenFile(Option<String>),
+    SetEncoding(Option<String>),
 
     PageScroll(isize),
     Move(bool, CursorMoveType),
@@ -52,6 +101,12 @@ impl From<&str> for CursorMoveType {
         match value {
             "^" => Self::Beg,
             "$" => Self::End,
+            "w" => Self::WordForward(false),
+            "W" => Self::WordForward(true),
+            "b" => Self::WordBackward(false),
+            "B" => Self::WordBackward(true),
+            "e" => Self::WordEnd(false),
+            "E" => Self::WordEnd(true),
             move_num => {
                 let _num = move_num.parse::<i16>()
                     .expect("Error code 1 when parsing &str to i16!");
@@ -62,26 +117,91 @@ impl From<&str> for CursorMoveType {
 }
 
 impl CursorMoveType {
+    /// Repeat the moving by number `count` times.
+    pub fn times(self, count: Option<usize>) -> Self {
+        match (self, count) {
+            (Self::Num(num), Some(count)) => Self::Num(
+                (num as isize).saturating_mul(count as isize)
+                    .clamp(i16::MIN as isize, i16::MAX as isize) as i16
+            ),
+            _ => self
+        }
+    }
+
+    /// Get the line which the buffer motion with `count` goes to, it's line `count`.
+    pub fn count_line(self, within_line: bool, count: Option<usize>) -> Option<usize> {
+        match (self, count) {
+            (Self::Beg | Self::End, Some(count)) if !within_line => Some(count.saturating_sub(1)),
+            _ => None
+        }
+    }
+
+    /// Return the cursor position after moving `count` times.
+    /// With a count, moving to the beginning or the end of buffer goes to line `count`.
+    pub async fn after_count_move(
+        self,
+        within_line: bool,
+        before: (usize, usize),
+        file_state: &mut crate::fs::FileState,
+        tab_width: usize,
+        count: Option<usize>
+    ) -> AppResult<(usize, usize)>
+    {
+        if let Some(line) = self.count_line(within_line, count) {
+            let content_len = file_state.content_ref().lock().await.len();
+            return Ok((0, line.min(content_len.saturating_sub(1))))
+        }
+
+        let count = match count {
+            Some(count) => count,
+            None => return self.after_move(within_line, before, file_state, tab_width).await,
+        };
+
+        match self {
+            Self::Num(_) => {
+                self.times(Some(count)).after_move(within_line, before, file_state, tab_width).await
+            },
+            Self::Beg | Self::End => self.after_move(within_line, before, file_state, tab_width).await,
+            _ => {
+                let mut after = before;
+
+                for _ in 0..count {
+                    let next = self.after_move(within_line, after, file_state, tab_width).await?;
+                    if next == after {
+                        break;
+                    }
+
+                    after = next;
+                }
+
+                Ok(after)
+            },
+        }
+    }
+
     /// Return the cursor position after current moving.
+    /// The cursor moved between lines is kept on the same display column.
     pub async fn after_move(
         self,
         within_line: bool,
-        before: (u16, u16),
-        file_state: &mut crate::fs::FileState
-    ) -> AppResult<(u16, u16)>
+        before: (usize, usize),
+        file_state: &mut crate::fs::FileState,
+        tab_width: usize
+    ) -> AppResult<(usize, usize)>
     {
-        if self == Self::Num(0) {
-            return Ok(before)
+        match self {
+            Self::Num(0) => return Ok(before),
+            Self::WordForward(bigword) => return word_forward(file_state, before, bigword).await,
+            Self::WordBackward(bigword) => return word_backward(file_state, before, bigword).await,
+            Self::WordEnd(bigword) => return word_end(file_state, before, bigword).await,
+            _ => ()
         }
 
         let (max, modify_ref);
         let mut after = before;
 
         if within_line {
-            max = file_state.get_lines(before.1, before.1)
-                .await?[0]
-                .chars()
-                .count() - 1;
+            max = grapheme_len(&file_state.get_lines(before.1, before.1).await?[0]) - 1;
             modify_ref = &mut after.0;
         } else {
             max = file_state.content_ref().lock().await.len() - 1;
@@ -90,14 +210,14 @@ impl CursorMoveType {
 
         match self {
             CursorMoveType::Num(i) => {
-                let after_move = *modify_ref as i16 + i;
+                let after_move = *modify_ref as isize + i as isize;
 
                 if after_move < 0 {
                     *modify_ref = 0;
                 } else if after_move as usize >= max {
-                    *modify_ref = max as u16;
+                    *modify_ref = max;
                 } else {
-                    *modify_ref = after_move as u16;
+                    *modify_ref = after_move as usize;
                 }
 
             },
@@ -108,21 +228,25 @@ impl CursorMoveType {
                 }
             },
             CursorMoveType::End => {
-                *modify_ref = max as u16;
+                *modify_ref = max;
                 if !within_line {
                     after.0 = 0;
                 }
             },
+            _ => unreachable!(),
         }
 
         if !within_line {
-            let new_line_length = file_state.get_lines(after.1, after.1)
-                .await?[0]
-                .chars()
-                .count() - 1;
+            let new_line = &file_state.get_lines(after.1, after.1).await?[0];
 
-            if after.0 > new_line_length as u16 {
-                after.0 = new_line_length as u16;
+            if let CursorMoveType::Num(_) = self {
+                let display_x = file_state.display_range(before, tab_width).await.start;
+                after.0 = display_to_grapheme(new_line, display_x, tab_width);
+            }
+
+            let new_line_length = grapheme_len(new_line) - 1;
+            if after.0 > new_line_length {
+                after.0 = new_line_length;
             }
         }
 
@@ -134,20 +258,59 @@ impl Command {
     // NOTE: Every command will return a boolean value. When it's true, update the
     // stylized content.
     pub async fn execute(&self, app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+        if self.is_mutating() {
+            check_writable(app)?;
+        }
+
+        if app.file_state.hex().is_some() {
+            if let Some(to_update) = self.execute_hex(app)? {
+                return Ok(to_update)
+            }
+        }
+
         Ok(match *self {
             Command::Mark                      => mark(app, key)?,
             Command::Save                      => save(app).await?,
             Command::Quit                      => quit(app, key).await,
+            Command::Undo                      => undo(app).await?,
+            Command::Redo                      => redo(app).await?,
+            Command::Yank                      => yank(app, key).await?,
+            Command::Paste(after)              => paste(app, after).await?,
+            Command::CopyClipboard             => copy_clipboard(app).await?,
+            Command::PasteClipboard(after)     => paste_clipboard(app, after).await?,
             Command::Change                    => change(app, key).await?,
-            Command::NewLine(down)             => newline(app, down).await,
+            Command::Reload                    => reload(app).await?,
+            Command::Recover                   => recover(app, key).await?,
+            Command::CreateDir                 => create_dir(app, key).await?,
+            Command::ToggleHex                 => toggle_hex(app).await?,
+            Command::ToggleReadonly            => toggle_readonly(app),
+            Command::DiskChange                => disk_change(app, key).await?,
+            Command::PickBuffer                => pick_buffer(app, key).await?,
+            Command::CloseBuffer               => close_buffer(app, key).await?,
+            Command::NewLine(down)             => newline(app, down).await?,
             Command::BackwardChar              => backward_char(app).await?,
             Command::EscapeCommand             => escape_command(app).await?,
+            Command::SelectRegister            => select_register(app, key)?,
+            Command::SwitchLineEnding          => switch_line_ending(app).await?,
             Command::ReplaceChar               => replace_char(app, key).await?,
             Command::SearchJump(next)          => search_jump(app, next).await?,
+            Command::SwitchBuffer(next)        => switch_buffer(app, next).await?,
             Command::PageScroll(move_line)     => page_scroll(app, move_line).await,
+            Command::MoveNibble(num)           => move_nibble(app, num).await?,
             Command::Search(ref pattern)       => search(app, pattern.to_owned()).await?,
+            Command::OpenFile(ref path)        => open_file(app, path.to_owned()).await?,
+            Command::SaveAs(ref path)          => save_as(app, path.to_owned()).await?,
+            Command::Overwrite(ref path)       => overwrite(app, path.to_owned(), key).await?,
+            Command::SetEncoding(ref label)    => set_encoding(app, label.to_owned()).await?,
             Command::ChangeInsert(cursor_move) => change_insert(app, cursor_move).await?,
 
+            Command::TextObject(operator, inner) => text_object(
+                app,
+                operator,
+                inner,
+                key
+            ).await?,
+
             Command::Move(within_line, cursor_move) => move_cursor(
                 app,
                 within_line,
@@ -163,4 +326,145 @@ impl Command {
             }
         })
     }
+
+    /// Whether the command modifies the content or the file.
+    fn is_mutating(&self) -> bool {
+        matches!(
+            *self,
+            Command::Save |
+            Command::SaveAs(_) |
+            Command::Overwrite(_) |
+            Command::CreateDir |
+            Command::Undo |
+            Command::Redo |
+            Command::Change |
+            Command::Paste(_) |
+            Command::PasteClipboard(_) |
+            Command::Delete(_) |
+            Command::TextObject(ObjectOperator::Delete | ObjectOperator::Change, _) |
+            Command::NewLine(_) |
+            Command::ReplaceChar |
+            Command::BackwardChar |
+            Command::ChangeInsert(_) |
+            Command::SetEncoding(_) |
+            Command::SwitchLineEnding
+        )
+    }
+
+    /// Execute the command in hex view.
+    /// Return None when the command works the same as in text view.
+    fn execute_hex(&self, app: &mut App) -> AppResult<Option<bool>> {
+        Ok(Some(match *self {
+            Command::Move(within_line, cursor_move) => hex_move(
+                app,
+                within_line,
+                cursor_move,
+                app.count
+            ),
+
+            // Bytes can only be overwritten in hex view.
+            Command::ChangeInsert(_) => {
+                app.get_modal().switch_insert();
+                false
+            },
+
+            Command::Save |
+            Command::Quit |
+            Command::Reload |
+            Command::Recover |
+            Command::CreateDir |
+            Command::ToggleHex |
+            Command::DiskChange |
+            Command::PickBuffer |
+            Command::CloseBuffer |
+            Command::ToggleReadonly |
+            Command::EscapeCommand |
+            Command::SwitchLineEnding |
+            Command::MoveNibble(_) |
+            Command::OpenFile(_) |
+            Command::SaveAs(_) |
+            Command::Overwrite(_) |
+            Command::SwitchBuffer(_) |
+            Command::PageScroll(_) |
+            Command::SetEncoding(_) => return Ok(None),
+
+            _ => return Err(
+                ErrorType::Specific(
+                    String::from("This command is not available in hex view")
+                ).pack()
+            ),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{fs::{FileState, TextBuffer}, utils::TempDir};
+
+    use super::*;
+
+    async fn count_move(
+        file_state: &mut FileState,
+        cursor_move: CursorMoveType,
+        before: (usize, usize),
+        count: Option<usize>
+    ) -> (usize, usize)
+    {
+        cursor_move.after_count_move(false, before, file_state, 4, count).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn readonly_test() {
+        let dir = TempDir::new("readonly_test");
+        let path = dir.join("file.txt");
+
+        let mut app = App::new();
+        app.file_state.set_readonly(true);
+        let source = app.file_state.path().map(Path::to_path_buf);
+
+        let save_as = Command::SaveAs(Some(path.to_string_lossy().into_owned()));
+        assert!(save_as.execute(&mut app, None).await.is_err());
+        let confirm = Some(KeyCode::Char('y'));
+        assert!(Command::Overwrite(String::new()).execute(&mut app, confirm).await.is_err());
+        assert!(Command::CreateDir.execute(&mut app, confirm).await.is_err());
+
+        assert!(!path.exists());
+        assert_eq!(app.file_state.path().map(Path::to_path_buf), source);
+    }
+
+    #[tokio::test]
+    async fn count_move_test() {
+        use CursorMoveType::*;
+
+        assert_eq!(Num(-5).times(Some(3)), Num(-15));
+        assert_eq!(Num(5).times(Some(99_999)), Num(i16::MAX));
+        assert_eq!(Num(1).times(None), Num(1));
+        assert_eq!(End.times(Some(3)), End);
+
+        let mut file_state = FileState::default();
+        *file_state.content_ref().lock().await = TextBuffer::from_text("one two\nthree\nfour\n");
+
+        assert_eq!(count_move(&mut file_state, End, (3, 0), Some(2)).await, (0, 1));
+        assert_eq!(count_move(&mut file_state, Beg, (3, 0), Some(9)).await, (0, 2));
+        assert_eq!(count_move(&mut file_state, End, (3, 0), None).await, (0, 2));
+        assert_eq!(count_move(&mut file_state, Num(1), (0, 0), Some(2)).await, (0, 2));
+        assert_eq!(count_move(&mut file_state, WordForward(false), (0, 0), Some(2)).await, (0, 1));
+        assert_eq!(count_move(&mut file_state, WordForward(false), (0, 0), Some(50)).await, (4, 2));
+        assert_eq!(count_move(&mut file_state, WordBackward(false), (2, 2), Some(50)).await, (0, 0));
+
+        // The counted buffer motion goes to the row in hex view.
+        let mut app = App::new();
+        *app.file_state.content_ref().lock().await = TextBuffer::from_text(&"0123456789\n".repeat(4));
+        app.file_state.toggle_hex((0, 0)).await.unwrap();
+        *app.editor_state.cursor_mut() = (5, 0);
+
+        let cases = [(End, Some(2), (0, 1)), (Beg, Some(9), (0, 2)), (End, None, (11, 2))];
+        for (cursor_move, count, cursor) in cases {
+            app.count = count;
+            Command::Move(false, cursor_move).execute(&mut app, None).await.unwrap();
+            assert_eq!(app.editor_state.cursor(), cursor);
+        }
+    }
 }
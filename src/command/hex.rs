@@ -0,0 +1,131 @@
+// Hex Commands
+
+use ratatui::crossterm::event::KeyCode;
+
+use crate::{app::App, error::AppResult, fs::HexBuffer};
+
+use super::{basic::move_cursor, CursorMoveType};
+
+/// Switch between text view & hex view.
+pub async fn toggle_hex(app: &mut App) -> AppResult<bool> {
+    let cursor_after = app.file_state.toggle_hex(app.editor_state.cursor()).await?;
+
+    *app.editor_state.cursor_mut() = cursor_after;
+    *app.editor_state.mark_mut() = None;
+    app.editor_state.hex_cursor = Default::default();
+    app.editor_state.update_linenr(
+        app.file_state.content_ref().lock().await.len()
+    );
+
+    Ok(true)
+}
+
+/// Move cursor by bytes or rows in hex view.
+pub fn hex_move(
+    app: &mut App,
+    within_line: bool,
+    cursor_move: CursorMoveType,
+    count: Option<usize>
+) -> bool
+{
+    let (col, row) = app.editor_state.cursor();
+    let idx = row * HexBuffer::ROW_SIZE + col;
+    let row_start = row * HexBuffer::ROW_SIZE;
+
+    // Like lines in text view, the counted buffer motion goes to the row.
+    if let Some(row) = cursor_move.count_line(within_line, count) {
+        let last_row = hex_len(app).saturating_sub(1) / HexBuffer::ROW_SIZE;
+        set_hex_cursor(app, (row.min(last_row) * HexBuffer::ROW_SIZE) as isize);
+        app.editor_state.hex_cursor.low_nibble = false;
+
+        return false
+    }
+
+    let idx_after = match (within_line, cursor_move.times(count)) {
+        (true, CursorMoveType::Num(num)) => idx as isize + num as isize,
+        (true, CursorMoveType::Beg) => row_start as isize,
+        (true, CursorMoveType::End) => (row_start + HexBuffer::ROW_SIZE - 1) as isize,
+        (false, CursorMoveType::Num(num)) => {
+            idx as isize + num as isize * HexBuffer::ROW_SIZE as isize
+        },
+        (false, CursorMoveType::Beg) => 0,
+        (false, CursorMoveType::End) => isize::MAX,
+
+        // There's no word in bytes.
+        _ => return false,
+    };
+
+    set_hex_cursor(app, idx_after);
+    app.editor_state.hex_cursor.low_nibble = false;
+
+    false
+}
+
+/// Move cursor by nibbles in hex view, or by chars in text view.
+pub async fn move_nibble(app: &mut App, num: isize) -> AppResult<bool> {
+    if app.file_state.hex().is_none() {
+        return move_cursor(app, true, CursorMoveType::Num(num as i16)).await
+    }
+
+    let hex_cursor = app.editor_state.hex_cursor;
+    if hex_cursor.ascii_pane {
+        return Ok(hex_move(app, true, CursorMoveType::Num(num as i16), app.count))
+    }
+
+    let num = num * app.count.unwrap_or(1) as isize;
+
+    let (col, row) = app.editor_state.cursor();
+    let nibble = ((row * HexBuffer::ROW_SIZE + col) * 2) as isize +
+        hex_cursor.low_nibble as isize +
+        num;
+    let nibble = nibble.clamp(0, (hex_len(app) * 2).saturating_sub(1) as isize);
+
+    set_hex_cursor(app, nibble / 2);
+    app.editor_state.hex_cursor.low_nibble = nibble % 2 == 1;
+
+    Ok(false)
+}
+
+/// Overwrite the bytes with the key pressed in insert modal of hex view.
+/// The hex digits are typed in hex pane, and the ASCII chars are typed in ASCII pane.
+pub async fn hex_input(app: &mut App, key: KeyCode) -> AppResult<bool> {
+    let (col, row) = app.editor_state.cursor();
+    let idx = row * HexBuffer::ROW_SIZE + col;
+    let ascii_pane = app.editor_state.hex_cursor.ascii_pane;
+
+    match key {
+        KeyCode::Tab => {
+            app.editor_state.hex_cursor.ascii_pane = !ascii_pane;
+            app.editor_state.hex_cursor.low_nibble = false;
+        },
+        KeyCode::Backspace | KeyCode::Left => { move_nibble(app, -1).await?; },
+        KeyCode::Right => { move_nibble(app, 1).await?; },
+
+        KeyCode::Char(_char) if ascii_pane && _char.is_ascii() => {
+            app.file_state.set_hex_byte(idx, _char as u8).await;
+            hex_move(app, true, CursorMoveType::Num(1), None);
+        },
+        KeyCode::Char(_char) if !ascii_pane => {
+            if let Some(value) = _char.to_digit(16) {
+                let low_nibble = app.editor_state.hex_cursor.low_nibble;
+                app.file_state.set_hex_nibble(idx, low_nibble, value as u8).await;
+                move_nibble(app, 1).await?;
+            }
+        },
+        _ => ()
+    }
+
+    Ok(false)
+}
+
+fn hex_len(app: &App) -> usize {
+    app.file_state.hex().map_or(0, HexBuffer::len)
+}
+
+/// Move cursor to the byte at `idx`, which is limited within the content.
+fn set_hex_cursor(app: &mut App, idx: isize) {
+    let max = hex_len(app).saturating_sub(1) as isize;
+    let idx = idx.clamp(0, max) as usize;
+
+    *app.editor_state.cursor_mut() = (idx % HexBuffer::ROW_SIZE, idx / HexBuffer::ROW_SIZE);
+}
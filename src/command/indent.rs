@@ -0,0 +1,148 @@
+// Indent
+
+use crate::{
+    app::App,
+    error::AppResult,
+    utils::{grapheme_len, grapheme_to_byte}
+};
+
+/// The syntaxes whose blocks are opened by a colon.
+const COLON_SYNTAXES: [&str; 3] = ["Python", "YAML", "Nim"];
+
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Split the line at cursor, the new line is indented as the current one.
+/// A level is added after an opening bracket or a colon with smart indent, and the
+/// closing bracket right after cursor is moved to another line.
+pub async fn split_line(app: &mut App) -> AppResult<bool> {
+    let cursor = app.editor_state.cursor();
+    let line = app.file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+    let (before, after) = line.split_at(grapheme_to_byte(&line, cursor.0));
+
+    let leading = leading_whitespace(before);
+    let mut indent = String::from(leading);
+    let mut closing = false;
+
+    if let Some(opener) = block_opener(app, before).await {
+        indent.push_str(&indent_unit(app));
+        closing = BRACKETS.iter().any(|&(_opener, closer)| {
+            _opener == opener && after.trim_start().starts_with(closer)
+        });
+    }
+
+    let text = if closing {
+        format!("\n{}\n{}", indent, leading)
+    } else {
+        format!("\n{}", indent)
+    };
+
+    app.file_state.insert_text(cursor, &text).await?;
+    *app.editor_state.cursor_mut() = (grapheme_len(&indent), cursor.1 + 1);
+
+    Ok(true)
+}
+
+/// Get the indentation of the line opened below or above line `idx`.
+pub async fn newline_indent(app: &App, idx: usize, down: bool) -> AppResult<String> {
+    if !app.options().auto_indent {
+        return Ok(String::new())
+    }
+
+    let line = app.file_state.get_lines(idx, idx).await?.remove(0);
+    let mut indent = String::from(leading_whitespace(&line));
+
+    if down && block_opener(app, &line).await.is_some() {
+        indent.push_str(&indent_unit(app));
+    }
+
+    Ok(indent)
+}
+
+/// Remove a level of indentation before the closing bracket to be typed at cursor,
+/// which is only done when there's nothing but whitespaces before cursor.
+/// Return the cursor after dedenting.
+pub async fn dedent_closer(app: &mut App) -> AppResult<(usize, usize)> {
+    let cursor = app.editor_state.cursor();
+    if !app.options().smart_indent || cursor.0 == 0 {
+        return Ok(cursor)
+    }
+
+    let line = app.file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+    let before = &line[..grapheme_to_byte(&line, cursor.0)];
+    if !before.trim_start().is_empty() {
+        return Ok(cursor)
+    }
+
+    let dedented = remove_level(before, app.options().tab_width);
+    app.file_state.replace_text((0, cursor.1), cursor, dedented).await?;
+
+    Ok((grapheme_len(dedented), cursor.1))
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    let end = line.find(|_char: char| !matches!(_char, ' ' | '\t'))
+        .unwrap_or(line.len());
+
+    &line[..end]
+}
+
+fn indent_unit(app: &App) -> String {
+    if app.options().tab_indent {
+        String::from("\t")
+    } else {
+        " ".repeat(app.options().tab_width)
+    }
+}
+
+/// Get the char opening a block at the end of `text` with smart indent.
+async fn block_opener(app: &App, text: &str) -> Option<char> {
+    if !app.options().smart_indent {
+        return None
+    }
+
+    let last = text.trim_end().chars().last()?;
+    if BRACKETS.iter().any(|(opener, _)| *opener == last) {
+        return Some(last)
+    }
+
+    if last == ':' {
+        let syntax = app.file_state.syntax_name().await?;
+        if COLON_SYNTAXES.contains(&syntax.as_str()) {
+            return Some(last)
+        }
+    }
+
+    None
+}
+
+/// Remove the last level of `indent`, which is a tab or spaces up to `tab_width`.
+fn remove_level(indent: &str, tab_width: usize) -> &str {
+    if let Some(rest) = indent.strip_suffix('\t') {
+        return rest
+    }
+
+    let spaces = indent.len() - indent.trim_end_matches(' ').len();
+    let width = match spaces % tab_width {
+        0 => tab_width.min(spaces),
+        rest => rest,
+    };
+
+    &indent[..indent.len() - width]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indent_test() {
+        assert_eq!(leading_whitespace("\t  fn main() {\n"), "\t  ");
+        assert_eq!(leading_whitespace("    \n"), "    ");
+
+        assert_eq!(remove_level("        ", 4), "    ");
+        assert_eq!(remove_level("      ", 4), "    ");
+        assert_eq!(remove_level("  ", 4), "");
+        assert_eq!(remove_level("\t\t", 4), "\t");
+        assert_eq!(remove_level("", 4), "");
+    }
+}
@@ -1,5 +1,12 @@
 pub(self) mod basic;
+mod buffer;
 pub(self) mod command_type;
+mod hex;
+mod indent;
+mod register;
+mod text_object;
+mod word;
 
 pub use command_type::{Command, CommandPrior, CursorMoveType};
 pub use basic::{insert_char, backward_char};
+pub use hex::hex_input;
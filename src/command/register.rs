@@ -0,0 +1,175 @@
+// Register Commands
+
+use ratatui::crossterm::event::KeyCode;
+
+use crate::{
+    app::{App, Register},
+    error::{AppResult, ErrorType},
+    utils::grapheme_len
+};
+
+use super::{basic::marked_region, CommandPrior};
+
+/// Select the register used by the next yanking, deleting or pasting.
+pub fn select_register(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    if key.is_none() {
+        app.prior_command = CommandPrior::SelectRegister;
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+
+    if let KeyCode::Char(name) = key.unwrap() {
+        if app.registers.select(name) {
+            return Ok(false)
+        }
+    }
+
+    Err(
+        ErrorType::Specific(
+            String::from("Invalid register name")
+        ).pack()
+    )
+}
+
+/// Yank the marked region, or the current line with `yy`.
+pub async fn yank(app: &mut App, key: Option<KeyCode>) -> AppResult<bool> {
+    let cursor = app.editor_state.cursor();
+
+    if key.is_none() {
+        match app.editor_state.mark() {
+            Some(mark_pos) => {
+                *app.editor_state.mark_mut() = None;
+
+                if let Some((start, end)) = marked_region(mark_pos, cursor) {
+                    let text = app.file_state.get_text(start, end).await?;
+                    app.registers.yank(Register::new(text, false));
+                    *app.editor_state.cursor_mut() = start;
+                }
+            },
+            None => app.prior_command = CommandPrior::Yank,
+        }
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+
+    match key.unwrap() {
+        KeyCode::Char('y') => {
+            let line = app.file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+            app.registers.yank(Register::new(line, true));
+        },
+        _ => return Err(
+            ErrorType::Specific(
+                String::from("Invalid key command")
+            ).pack()
+        )
+    }
+
+    Ok(false)
+}
+
+/// Paste the selected register after or before cursor.
+pub async fn paste(app: &mut App, after: bool) -> AppResult<bool> {
+    let register = match app.registers.get() {
+        Some(register) => register.to_owned(),
+        None => return Err(
+            ErrorType::Specific(
+                String::from("Nothing in register to paste")
+            ).pack()
+        ),
+    };
+
+    paste_register(app, register, after).await
+}
+
+/// Copy the marked region or the current line into system clipboard.
+pub async fn copy_clipboard(app: &mut App) -> AppResult<bool> {
+    let cursor = app.editor_state.cursor();
+
+    let text = match app.editor_state.mark() {
+        Some(mark_pos) => {
+            *app.editor_state.mark_mut() = None;
+
+            match marked_region(mark_pos, cursor) {
+                Some((start, end)) => {
+                    *app.editor_state.cursor_mut() = start;
+                    app.file_state.get_text(start, end).await?
+                },
+                None => return Ok(false),
+            }
+        },
+        None => app.file_state.get_lines(cursor.1, cursor.1).await?.remove(0),
+    };
+
+    let provider = app.options().clipboard.to_owned();
+    app.clipboard.copy(&provider, text).await?;
+
+    Ok(false)
+}
+
+/// Paste the text from system clipboard after or before cursor.
+/// The text ending with a line break is pasted as lines.
+pub async fn paste_clipboard(app: &mut App, after: bool) -> AppResult<bool> {
+    let text = app.clipboard.paste(&app.options().clipboard).await?
+        .filter(|text| !text.is_empty())
+        .map(|text| text.replace("\r\n", "\n"));
+
+    let register = match text {
+        Some(text) => {
+            let linewise = text.ends_with('\n');
+            Register::new(text, linewise)
+        },
+        None => return Err(
+            ErrorType::Specific(
+                String::from("Nothing in clipboard to paste")
+            ).pack()
+        ),
+    };
+
+    paste_register(app, register, after).await
+}
+
+/// The linewise text is pasted below or above current line.
+async fn paste_register(app: &mut App, register: Register, after: bool) -> AppResult<bool> {
+    let cursor = app.editor_state.cursor();
+    let file_length = app.file_state.content_ref().lock().await.len();
+
+    // The text is pasted as lines into an empty file.
+    if file_length == 0 {
+        let mut text = register.text;
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        app.file_state.insert_lines(0, vec![text]).await?;
+        *app.editor_state.cursor_mut() = (0, 0);
+
+        return Ok(true)
+    }
+
+    if register.linewise {
+        let line = if after { cursor.1 + 1 } else { cursor.1 };
+
+        app.file_state.insert_lines(line, vec![register.text]).await?;
+        *app.editor_state.cursor_mut() = (0, line);
+
+        return Ok(true)
+    }
+
+    // The text pasted after the end of line is placed before its line break.
+    let at = if after {
+        let line_length = grapheme_len(&app.file_state.get_lines(cursor.1, cursor.1).await?[0]);
+        ((cursor.0 + 1).min(line_length - 1), cursor.1)
+    } else {
+        cursor
+    };
+
+    // Cursor is placed on the last pasted char.
+    let end = app.file_state.insert_text(at, &register.text).await?;
+    *app.editor_state.cursor_mut() = (end.0.saturating_sub(1), end.1);
+
+    Ok(true)
+}
@@ -0,0 +1,400 @@
+// Text Object
+
+use ratatui::crossterm::event::KeyCode;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    app::{App, Register},
+    error::{AppResult, ErrorType},
+    fs::TextBuffer,
+    utils::grapheme_len
+};
+
+use super::{word::word_spans, CommandPrior};
+
+/// The command applied to the text object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectOperator {
+    Delete,
+    Change,
+    Mark
+}
+
+/// The range of text object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ObjectRange {
+    Chars((usize, usize), (usize, usize)),      // The end position is exclusive
+    Lines(usize, usize)
+}
+
+/// Apply `operator` to the inner or around text object named by `key`.
+pub async fn text_object(
+    app: &mut App,
+    operator: ObjectOperator,
+    inner: bool,
+    key: Option<KeyCode>
+) -> AppResult<bool>
+{
+    if key.is_none() {
+        app.prior_command = CommandPrior::TextObject(operator, inner);
+
+        return Ok(false)
+    }
+
+    app.prior_command = CommandPrior::None;
+
+    let name = match key.unwrap() {
+        KeyCode::Char(name) => name,
+        _ => return Err(
+            ErrorType::Specific(
+                String::from("Invalid key command")
+            ).pack()
+        )
+    };
+
+    let cursor = app.editor_state.cursor();
+    let content = app.file_state.content_ref().lock().await;
+
+    if content.is_empty() {
+        return Ok(false)
+    }
+
+    let object = match name {
+        'w' | 'W' => word_object(&content.line(cursor.1), cursor, name == 'W', inner),
+        '"' | '\'' | '`' => quote_object(&content.line(cursor.1), cursor, name, inner),
+        '(' | ')' | 'b' => bracket_object(&content, cursor, ('(', ')'), inner),
+        '[' | ']' => bracket_object(&content, cursor, ('[', ']'), inner),
+        '{' | '}' | 'B' => bracket_object(&content, cursor, ('{', '}'), inner),
+        '<' | '>' => bracket_object(&content, cursor, ('<', '>'), inner),
+        'p' => Some(paragraph_object(&content, cursor.1, inner)),
+        _ => return Err(
+            ErrorType::Specific(
+                String::from("Invalid key command")
+            ).pack()
+        )
+    };
+    drop(content);
+
+    let object = match object {
+        Some(object) => object,
+        None => return Err(
+            ErrorType::Specific(
+                String::from("Cannot find the text object")
+            ).pack()
+        ),
+    };
+
+    match operator {
+        ObjectOperator::Delete => delete_object(app, object, false).await,
+        ObjectOperator::Change => {
+            let to_update = delete_object(app, object, true).await?;
+            app.get_modal().switch_insert();
+
+            Ok(to_update)
+        },
+        ObjectOperator::Mark => {
+            mark_object(app, object).await?;
+
+            Ok(false)
+        },
+    }
+}
+
+/// Delete the text object into registers, an empty line is kept when changing lines.
+async fn delete_object(app: &mut App, object: ObjectRange, change: bool) -> AppResult<bool> {
+    match object {
+        ObjectRange::Chars(start, mut end) => {
+            // The line break before the closing bracket at the beginning of line is kept
+            // when changing, thus the text can be inserted in the line.
+            if change && end.0 == 0 && end.1 > start.1 {
+                let line = app.file_state.get_lines(end.1 - 1, end.1 - 1).await?.remove(0);
+                end = (grapheme_len(&line) - 1, end.1 - 1);
+            }
+
+            *app.editor_state.cursor_mut() = start;
+
+            if start >= end {
+                return Ok(false)
+            }
+
+            let removed = app.file_state.remove_text(start, end).await?;
+            app.registers.kill(Register::new(removed, false));
+        },
+        ObjectRange::Lines(from, to) => {
+            let lines = app.file_state.get_lines(from, to).await?.concat();
+            let replacement = if change { vec![String::from("\n")] } else { Vec::new() };
+
+            app.file_state.modify_lines(from, to, replacement).await?;
+            app.registers.kill(Register::new(lines, true));
+
+            let content_len = app.file_state.content_ref().lock().await.len();
+            *app.editor_state.cursor_mut() = (0, from.min(content_len.saturating_sub(1)));
+        },
+    }
+
+    Ok(true)
+}
+
+/// Mark the text object, whose last char is the mark point.
+async fn mark_object(app: &mut App, object: ObjectRange) -> AppResult<()> {
+    let (start, last) = match object {
+        ObjectRange::Chars(start, end) => {
+            if start >= end {
+                return Ok(())
+            }
+
+            let last = if end.0 > 0 {
+                (end.0 - 1, end.1)
+            } else {
+                let line = app.file_state.get_lines(end.1 - 1, end.1 - 1).await?.remove(0);
+                (grapheme_len(&line) - 1, end.1 - 1)
+            };
+
+            (start, last)
+        },
+        ObjectRange::Lines(from, to) => {
+            let line = app.file_state.get_lines(to, to).await?.remove(0);
+            ((0, from), (grapheme_len(&line) - 1, to))
+        },
+    };
+
+    *app.editor_state.cursor_mut() = start;
+    *app.editor_state.mark_mut() = Some(last);
+
+    Ok(())
+}
+
+/// The word at cursor, or the whitespaces at cursor when it's not on a word.
+/// Around object includes the whitespaces after the word, or the ones before it when
+/// there's nothing after.
+fn word_object(line: &str, cursor: (usize, usize), bigword: bool, inner: bool) -> Option<ObjectRange> {
+    let (col, y) = cursor;
+    let line_end = grapheme_len(line) - 1;
+    let spans = word_spans(line, bigword);
+
+    let range = match spans.iter().position(|span| span.contains(&col)) {
+        Some(idx) => {
+            let span = &spans[idx];
+            let next_start = spans.get(idx + 1).map_or(line_end, |next| next.start);
+            let prev_end = if idx > 0 { spans[idx - 1].end } else { 0 };
+
+            if inner {
+                span.start..span.end
+            } else if next_start > span.end {
+                span.start..next_start
+            } else {
+                prev_end..span.end
+            }
+        },
+        None => {
+            let start = spans.iter().rfind(|span| span.end <= col).map_or(0, |span| span.end);
+            let next = spans.iter().find(|span| span.start > col);
+            let end = next.map_or(line_end, |span| span.start);
+
+            match next {
+                Some(span) if !inner => start..span.end,
+                _ => start..end,
+            }
+        },
+    };
+
+    if range.is_empty() {
+        return None
+    }
+
+    Some(ObjectRange::Chars((range.start, y), (range.end, y)))
+}
+
+/// The quoted string in current line, which is the one around cursor or the first one after it.
+fn quote_object(line: &str, cursor: (usize, usize), quote: char, inner: bool) -> Option<ObjectRange> {
+    let graphemes = line.graphemes(true).collect::<Vec<_>>();
+    let quote = quote.to_string();
+
+    // The escaped quotes are ignored.
+    let quotes = graphemes.iter()
+        .enumerate()
+        .filter(|(idx, grapheme)| {
+            **grapheme == quote && (*idx == 0 || graphemes[idx - 1] != "\\")
+        })
+        .map(|(idx, _)| idx)
+        .collect::<Vec<_>>();
+
+    let (start, end) = quotes.chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|(_, end)| *end >= cursor.0)?;
+
+    Some(if inner {
+        ObjectRange::Chars((start + 1, cursor.1), (end, cursor.1))
+    } else {
+        ObjectRange::Chars((start, cursor.1), (end + 1, cursor.1))
+    })
+}
+
+/// The text within the brackets around cursor, which can cross lines.
+/// Inner object excludes the line break after the opening bracket and the indentation
+/// before the closing bracket.
+fn bracket_object(
+    content: &TextBuffer,
+    cursor: (usize, usize),
+    (opener, closer): (char, char),
+    inner: bool
+) -> Option<ObjectRange>
+{
+    let (opener, closer) = (opener.to_string(), closer.to_string());
+    let at_cursor = content.line(cursor.1)
+        .graphemes(true)
+        .nth(cursor.0)
+        .map(String::from);
+
+    let (open, close) = if at_cursor.as_ref() == Some(&closer) {
+        let open = find_bracket(content, cursor, (&closer, &opener), false)?;
+        (open, cursor)
+    } else if at_cursor.as_ref() == Some(&opener) {
+        (cursor, find_bracket(content, cursor, (&opener, &closer), true)?)
+    } else {
+        let open = find_bracket(content, cursor, (&closer, &opener), false)?;
+        (open, find_bracket(content, open, (&opener, &closer), true)?)
+    };
+
+    if !inner {
+        return Some(ObjectRange::Chars(open, (close.0 + 1, close.1)))
+    }
+
+    let mut start = (open.0 + 1, open.1);
+    let mut end = close;
+
+    if open.1 < close.1 {
+        if start.0 == grapheme_len(&content.line(open.1)) - 1 {
+            start = (0, open.1 + 1);
+        }
+
+        if content.line(close.1).graphemes(true).take(close.0).all(|grapheme| grapheme.trim().is_empty()) {
+            end = (0, close.1);
+        }
+    }
+
+    Some(ObjectRange::Chars(start, end.max(start)))
+}
+
+/// Find the unmatched `target` bracket from `pos` (exclusive), forward or backward.
+/// The `nested` brackets between them are skipped.
+fn find_bracket(
+    content: &TextBuffer,
+    pos: (usize, usize),
+    (nested, target): (&str, &str),
+    forward: bool
+) -> Option<(usize, usize)>
+{
+    let mut depth = 0;
+    let mut check = |grapheme: &str| {
+        if grapheme == nested {
+            depth += 1;
+        } else if grapheme == target {
+            if depth == 0 {
+                return true
+            }
+            depth -= 1;
+        }
+
+        false
+    };
+
+    if forward {
+        for y in pos.1..content.len() {
+            let skip = if y == pos.1 { pos.0 + 1 } else { 0 };
+
+            for (x, grapheme) in content.line(y).graphemes(true).enumerate().skip(skip) {
+                if check(grapheme) {
+                    return Some((x, y))
+                }
+            }
+        }
+    } else {
+        for y in (0..=pos.1).rev() {
+            let line = content.line(y);
+            let graphemes = line.graphemes(true).collect::<Vec<_>>();
+            let take = if y == pos.1 { pos.0 } else { graphemes.len() };
+
+            for x in (0..take).rev() {
+                if check(graphemes[x]) {
+                    return Some((x, y))
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// The lines of paragraph or blank lines at line `y`.
+/// Around object includes the blank lines after the paragraph, or the ones before it when
+/// there's nothing after. For blank lines, the paragraph after them is included.
+fn paragraph_object(content: &TextBuffer, y: usize, inner: bool) -> ObjectRange {
+    let is_blank = |idx: usize| content.line(idx).trim().is_empty();
+    let blank = is_blank(y);
+
+    let mut from = y;
+    while from > 0 && is_blank(from - 1) == blank {
+        from -= 1;
+    }
+
+    let mut to = y;
+    while to + 1 < content.len() && is_blank(to + 1) == blank {
+        to += 1;
+    }
+
+    if inner {
+        return ObjectRange::Lines(from, to)
+    }
+
+    if to + 1 < content.len() {
+        let next_blank = !blank;
+        to += 1;
+
+        while to + 1 < content.len() && is_blank(to + 1) == next_blank {
+            to += 1;
+        }
+    } else if !blank {
+        while from > 0 && is_blank(from - 1) {
+            from -= 1;
+        }
+    }
+
+    ObjectRange::Lines(from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_object_test() {
+        let line = "let x = foo(\"a \\\" b\", 1);\n";
+
+        assert_eq!(word_object(line, (8, 0), false, true), Some(ObjectRange::Chars((8, 0), (11, 0))));
+        assert_eq!(word_object(line, (5, 0), false, false), Some(ObjectRange::Chars((5, 0), (7, 0))));
+        assert_eq!(word_object(line, (6, 0), false, false), Some(ObjectRange::Chars((6, 0), (8, 0))));
+        assert_eq!(quote_object(line, (2, 0), '"', true), Some(ObjectRange::Chars((13, 0), (19, 0))));
+        assert_eq!(quote_object(line, (2, 0), '\'', true), None);
+
+        let content = TextBuffer::from_text("fn main() {\n    foo(a, (b));\n}\n\n\nnext\n");
+        assert_eq!(
+            bracket_object(&content, (9, 1), ('(', ')'), true),
+            Some(ObjectRange::Chars((8, 1), (14, 1)))
+        );
+        assert_eq!(
+            bracket_object(&content, (4, 1), ('{', '}'), true),
+            Some(ObjectRange::Chars((0, 1), (0, 2)))
+        );
+        assert_eq!(
+            bracket_object(&content, (0, 2), ('{', '}'), false),
+            Some(ObjectRange::Chars((10, 0), (1, 2)))
+        );
+        assert_eq!(bracket_object(&content, (0, 5), ('(', ')'), true), None);
+
+        assert_eq!(paragraph_object(&content, 1, true), ObjectRange::Lines(0, 2));
+        assert_eq!(paragraph_object(&content, 1, false), ObjectRange::Lines(0, 4));
+        assert_eq!(paragraph_object(&content, 3, false), ObjectRange::Lines(3, 5));
+        assert_eq!(paragraph_object(&content, 5, false), ObjectRange::Lines(3, 5));
+    }
+}
@@ -0,0 +1,162 @@
+// Word Motion
+
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    error::AppResult,
+    fs::FileState,
+    utils::grapheme_len
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Space,
+    Word,
+    Punct
+}
+
+impl CharClass {
+    fn of(grapheme: &str) -> Self {
+        match grapheme.chars().next() {
+            Some(_char) if _char.is_whitespace() => Self::Space,
+            Some(_char) if _char.is_alphanumeric() || _char == '_' => Self::Word,
+            _ => Self::Punct,
+        }
+    }
+}
+
+/// Get the grapheme ranges of the words in `line`.
+/// A word is split at Unicode word boundaries, and a run of punctuations is a word as well.
+/// A WORD (`bigword`) is a run of non-whitespace graphemes.
+pub fn word_spans(line: &str, bigword: bool) -> Vec<Range<usize>> {
+    let mut spans: Vec<(Range<usize>, CharClass)> = Vec::new();
+    let mut col = 0;
+
+    for segment in line.split_word_bounds() {
+        for (idx, grapheme) in segment.graphemes(true).enumerate() {
+            let class = CharClass::of(grapheme);
+            col += 1;
+
+            if class == CharClass::Space {
+                continue
+            }
+
+            // Words are joined within their segment, while punctuations are always joined.
+            if let Some((span, span_class)) = spans.last_mut() {
+                if span.end == col - 1 && (
+                    bigword ||
+                    (*span_class == class && (class == CharClass::Punct || idx > 0))
+                ) {
+                    span.end = col;
+                    continue
+                }
+            }
+
+            spans.push((col - 1..col, class));
+        }
+    }
+
+    spans.into_iter().map(|(span, _)| span).collect()
+}
+
+/// Get the beginning of the next word, an empty line is seen as a word.
+/// Return the end of content when there's no word after cursor.
+pub async fn word_forward(
+    file_state: &FileState,
+    cursor: (usize, usize),
+    bigword: bool
+) -> AppResult<(usize, usize)>
+{
+    let line = file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+    if let Some(span) = word_spans(&line, bigword).into_iter().find(|span| span.start > cursor.0) {
+        return Ok((span.start, cursor.1))
+    }
+
+    let content_len = file_state.content_ref().lock().await.len();
+
+    for idx in cursor.1 + 1..content_len {
+        let line = file_state.get_lines(idx, idx).await?.remove(0);
+        if line == "\n" {
+            return Ok((0, idx))
+        }
+
+        if let Some(span) = word_spans(&line, bigword).first() {
+            return Ok((span.start, idx))
+        }
+    }
+
+    let last = file_state.get_lines(content_len - 1, content_len - 1).await?.remove(0);
+
+    Ok((grapheme_len(&last) - 1, content_len - 1))
+}
+
+/// Get the beginning of the word before cursor, an empty line is seen as a word.
+pub async fn word_backward(
+    file_state: &FileState,
+    cursor: (usize, usize),
+    bigword: bool
+) -> AppResult<(usize, usize)>
+{
+    let line = file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+    if let Some(span) = word_spans(&line, bigword).into_iter().rfind(|span| span.start < cursor.0) {
+        return Ok((span.start, cursor.1))
+    }
+
+    for idx in (0..cursor.1).rev() {
+        let line = file_state.get_lines(idx, idx).await?.remove(0);
+        if line == "\n" {
+            return Ok((0, idx))
+        }
+
+        if let Some(span) = word_spans(&line, bigword).last() {
+            return Ok((span.start, idx))
+        }
+    }
+
+    Ok((0, 0))
+}
+
+/// Get the end of the word after cursor, the cursor is kept when there's no such word.
+pub async fn word_end(
+    file_state: &FileState,
+    cursor: (usize, usize),
+    bigword: bool
+) -> AppResult<(usize, usize)>
+{
+    let line = file_state.get_lines(cursor.1, cursor.1).await?.remove(0);
+    if let Some(span) = word_spans(&line, bigword).into_iter().find(|span| span.end - 1 > cursor.0) {
+        return Ok((span.end - 1, cursor.1))
+    }
+
+    let content_len = file_state.content_ref().lock().await.len();
+
+    for idx in cursor.1 + 1..content_len {
+        let line = file_state.get_lines(idx, idx).await?.remove(0);
+
+        if let Some(span) = word_spans(&line, bigword).first() {
+            return Ok((span.end - 1, idx))
+        }
+    }
+
+    Ok(cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_test() {
+        let line = "let x_1 = self.foo(\"漢字\");\n";
+
+        assert_eq!(
+            word_spans(line, false),
+            vec![0..3, 4..7, 8..9, 10..14, 14..15, 15..18, 18..20, 20..21, 21..22, 22..25]
+        );
+        assert_eq!(word_spans(line, true), vec![0..3, 4..7, 8..9, 10..25]);
+        assert_eq!(word_spans("  -> can't\n", false), vec![2..4, 5..8, 8..9, 9..10]);
+        assert!(word_spans(" \t\n", false).is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+// Clipboard Provider
+
+use toml_edit::Item;
+
+use crate::{config_throw_error, error::{AppResult, ErrorType}};
+
+/// The way of accessing system clipboard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ClipboardProvider {
+    // Copy with the escape sequence of terminal, which works over SSH & tmux.
+    // Pasting from system clipboard is not supported.
+    #[default]
+    Osc52,
+
+    // External commands like wl-copy & wl-paste, the copied text is written into
+    // the stdin of `copy`, and the text to be pasted is read from the stdout of `paste`.
+    Command {
+        copy: Vec<String>,
+        paste: Option<Vec<String>>
+    },
+
+    // Only keep the copied text within tetor.
+    Internal
+}
+
+impl ClipboardProvider {
+    pub fn init(&mut self, clipboard: &Item) -> AppResult<()> {
+        let panic_str = "Wrong format for clipboard in config file!";
+        let clipboard = config_throw_error!(clipboard.as_table(), panic_str);
+
+        let mut provider = "osc52";
+        let (mut copy, mut paste) = (None, None);
+
+        for (prop, value) in clipboard.iter() {
+            match prop {
+                "provider" => provider = config_throw_error!(value.as_str(), panic_str),
+                "copy" => copy = Some(Self::parse_command(value)?),
+                "paste" => paste = Some(Self::parse_command(value)?),
+                _ => return Err(
+                    ErrorType::Specific(
+                        format!("Unknow clipboard option: {}", prop)
+                    ).pack()
+                )
+            }
+        }
+
+        *self = match provider {
+            "osc52" => Self::Osc52,
+            "internal" => Self::Internal,
+            "command" => Self::Command {
+                copy: copy.filter(|copy| !copy.is_empty()).ok_or_else(|| {
+                    ErrorType::Specific(
+                        String::from("The copy command is required by command clipboard provider")
+                    ).pack()
+                })?,
+                paste: paste.filter(|paste| !paste.is_empty())
+            },
+            _ => return Err(
+                ErrorType::Specific(
+                    format!("Unknow clipboard provider: {}", provider)
+                ).pack()
+            )
+        };
+
+        Ok(())
+    }
+
+    /// The command is either an array of arguments, or a string split like shell.
+    fn parse_command(command: &Item) -> AppResult<Vec<String>> {
+        let panic_str = "Wrong format for clipboard command in config file!";
+
+        if let Some(args) = command.as_array() {
+            return args.iter()
+                .map(|arg| arg.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| ErrorType::Specific(String::from(panic_str)).pack())
+        }
+
+        let command = config_throw_error!(command.as_str(), panic_str);
+
+        Ok(config_throw_error!(Self::split_command(command), panic_str))
+    }
+
+    /// Split the arguments at whitespaces outside of quotes, `\` escapes the next char
+    /// except within single quotes. Return None when a quote is not closed.
+    fn split_command(command: &str) -> Option<Vec<String>> {
+        let mut args = Vec::new();
+        let mut arg: Option<String> = None;
+        let mut quote = None;
+        let mut chars = command.chars();
+
+        while let Some(_char) = chars.next() {
+            match (quote, _char) {
+                (Some(_quote), _) if _char == _quote => quote = None,
+                (None | Some('"'), '\\') => arg.get_or_insert_with(String::new).push(chars.next()?),
+                (None, '\'' | '"') => {
+                    quote = Some(_char);
+                    arg.get_or_insert_with(String::new);
+                },
+                (None, _) if _char.is_whitespace() => args.extend(arg.take()),
+                _ => arg.get_or_insert_with(String::new).push(_char),
+            }
+        }
+
+        if quote.is_some() {
+            return None
+        }
+
+        args.extend(arg);
+
+        Some(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use toml_edit::DocumentMut;
+
+    use super::*;
+
+    #[test]
+    fn clipboard_config_test() {
+        let document = r#"
+            [clipboard]
+            provider = "command"
+            copy = "sh -c 'xclip -selection clipboard' \"\" a\\ b"
+            paste = ["/opt/my tools/paste", "--no-newline"]
+        "#.parse::<DocumentMut>().unwrap();
+
+        let mut provider = ClipboardProvider::default();
+        provider.init(&document["clipboard"]).unwrap();
+        assert_eq!(provider, ClipboardProvider::Command {
+            copy: vec![
+                String::from("sh"),
+                String::from("-c"),
+                String::from("xclip -selection clipboard"),
+                String::new(),
+                String::from("a b")
+            ],
+            paste: Some(vec![String::from("/opt/my tools/paste"), String::from("--no-newline")])
+        });
+
+        let document = "[clipboard]\nprovider = \"command\"\ncopy = \"sh -c 'xclip\"".parse::<DocumentMut>().unwrap();
+        assert!(provider.init(&document["clipboard"]).is_err());
+    }
+}
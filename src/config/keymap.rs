@@ -85,17 +85,34 @@ impl From<&str> for Command {
             "save"           => Self::Save,
             "mark"           => Self::Mark,
             "quit"           => Self::Quit,
+            "undo"           => Self::Undo,
+            "redo"           => Self::Redo,
+            "yank"           => Self::Yank,
+            "reload"         => Self::Reload,
+            "toggle_hex"     => Self::ToggleHex,
+            "toggle_readonly" => Self::ToggleReadonly,
+            "pick_buffer"    => Self::PickBuffer,
+            "close_buffer"   => Self::CloseBuffer,
             "change"         => Self::Change,
             "replace_char"   => Self::ReplaceChar,
             "backward_char"  => Self::BackwardChar,
             "escape_command" => Self::EscapeCommand,
+            "select_register" => Self::SelectRegister,
+            "copy_clipboard" => Self::CopyClipboard,
+            "switch_line_ending" => Self::SwitchLineEnding,
 
             "delete_char"    => Self::Delete(true),
             "search"         => Self::Search(None),
             "delete"         => Self::Delete(false),
+            "paste"          => Self::Paste(command_slice[1] == "after"),
+            "paste_clipboard" => Self::PasteClipboard(command_slice[1] == "after"),
             "newline"        => Self::NewLine(command_slice[1] == "down"),
             "change_insert"  => Self::ChangeInsert(command_slice[1].into()),
             "search_jump"    => Self::SearchJump(command_slice[1] == "next"),
+            "set_encoding"   => Self::SetEncoding(None),
+            "open_file"      => Self::OpenFile(None),
+            "save_as"        => Self::SaveAs(None),
+            "switch_buffer"  => Self::SwitchBuffer(command_slice[1] == "next"),
 
             "page_scroll" => {
                 let scroll_line = command_slice[1].parse::<isize>()
@@ -104,6 +121,13 @@ impl From<&str> for Command {
                 Self::PageScroll(scroll_line)
             },
 
+            "move_nibble" => {
+                let move_num = command_slice[1].parse::<isize>()
+                    .expect("Invalid argument for command move_nibble!");
+
+                Self::MoveNibble(move_num)
+            },
+
             "move_cursor" => {
                 let within_line = match command_slice[1] {
                     "line" => true,
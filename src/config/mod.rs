@@ -1,4 +1,6 @@
+mod clipboard;
 mod keymap;
+mod on_save;
 mod option;
 
 use std::{path::PathBuf, str::FromStr};
@@ -8,7 +10,9 @@ use toml_edit::DocumentMut;
 
 use crate::error::AppResult;
 
+pub use clipboard::ClipboardProvider;
 pub use keymap::Keymap;
+pub use on_save::{OnSave, SaveTransform};
 pub use option::*;
 
 #[macro_export]
@@ -0,0 +1,86 @@
+// On Save
+
+use std::collections::HashMap;
+
+use toml_edit::{Item, Table};
+
+use crate::{config_throw_error, error::{AppResult, ErrorType}};
+
+/// The transformations applied to content before saving.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaveTransform {
+    pub trim_trailing_whitespace: bool,
+    pub final_newline: bool,
+    pub trim_trailing_lines: bool,
+    pub convert_indent: bool        // Convert indentation into the style of tab_indent
+}
+
+/// The transformations for all files, which can be overridden for each file type.
+/// The file types are named by their syntaxes, such as "Rust" & "Markdown".
+#[derive(Debug, Clone, Default)]
+pub struct OnSave {
+    global: SaveTransform,
+    filetypes: HashMap<String, SaveTransform>
+}
+
+impl SaveTransform {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Override the transformations set in `table`.
+    fn init(&mut self, table: &Table, panic_str: &str) -> AppResult<()> {
+        for (prop, value) in table.iter() {
+            // The tables of file types are not transformations.
+            if prop == "filetype" {
+                continue
+            }
+
+            let enabled = config_throw_error!(value.as_bool(), panic_str);
+
+            match prop {
+                "trim_trailing_whitespace" => self.trim_trailing_whitespace = enabled,
+                "final_newline" => self.final_newline = enabled,
+                "trim_trailing_lines" => self.trim_trailing_lines = enabled,
+                "convert_indent" => self.convert_indent = enabled,
+                _ => return Err(
+                    ErrorType::Specific(
+                        format!("Unknow on_save option: {}", prop)
+                    ).pack()
+                )
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl OnSave {
+    pub fn init(&mut self, on_save: &Item) -> AppResult<()> {
+        let panic_str = "Wrong format for on_save in config file!";
+        let on_save = config_throw_error!(on_save.as_table(), panic_str);
+
+        self.global.init(on_save, panic_str)?;
+
+        let filetypes = match on_save.get("filetype") {
+            Some(filetypes) => config_throw_error!(filetypes.as_table(), panic_str),
+            None => return Ok(()),
+        };
+
+        for (filetype, table) in filetypes.iter() {
+            let mut transform = self.global;
+            transform.init(config_throw_error!(table.as_table(), panic_str), panic_str)?;
+
+            self.filetypes.insert(String::from(filetype), transform);
+        }
+
+        Ok(())
+    }
+
+    /// Get the transformations for the file highlighted with `syntax`.
+    pub fn get(&self, syntax: Option<&str>) -> SaveTransform {
+        syntax.and_then(|syntax| self.filetypes.get(syntax))
+            .copied()
+            .unwrap_or(self.global)
+    }
+}
@@ -2,23 +2,53 @@
 
 use toml_edit::DocumentMut;
 
-use crate::{config_throw_error, error::{AppResult, ErrorType}};
+use crate::{
+    config_throw_error,
+    error::{AppResult, ErrorType},
+    utils::DEFAULT_TAB_WIDTH
+};
+
+use super::{ClipboardProvider, OnSave};
 
 #[derive(Debug, Clone)]
 pub struct AppOption {
-    pub tab_indent: bool
+    pub tab_indent: bool,
+    pub tab_width: usize,
+    pub auto_indent: bool,
+    pub smart_indent: bool,         // Indent by brackets & colons
+    pub backup: bool,
+    pub remember_position: bool,
+    pub max_positions: usize,       // The count of files whose positions are remembered
+    pub on_save: OnSave,
+    pub clipboard: ClipboardProvider
 }
 
 impl Default for AppOption {
     fn default() -> Self {
         Self {
-            tab_indent: false
+            tab_indent: false,
+            tab_width: DEFAULT_TAB_WIDTH,
+            auto_indent: true,
+            smart_indent: true,
+            backup: false,
+            remember_position: true,
+            max_positions: 100,
+            on_save: OnSave::default(),
+            clipboard: ClipboardProvider::default()
         }
     }
 }
 
 impl AppOption {
     pub fn init(&mut self, document: &DocumentMut) -> AppResult<()> {
+        if let Some(on_save) = document["config"].get("on_save") {
+            self.on_save.init(on_save)?;
+        }
+
+        if let Some(clipboard) = document["config"].get("clipboard") {
+            self.clipboard.init(clipboard)?;
+        }
+
         let panic_str = "Wrong format for App Options in config file!";
         let options = document["config"].get("options");
 
@@ -37,6 +67,32 @@ impl AppOption {
                     value.as_bool(),
                     panic_str
                 ),
+                "tab_width" => self.tab_width = config_throw_error!(
+                    value.as_integer()
+                        .filter(|value| *value > 0)
+                        .and_then(|value| usize::try_from(value).ok()),
+                    panic_str
+                ),
+                "auto_indent" => self.auto_indent = config_throw_error!(
+                    value.as_bool(),
+                    panic_str
+                ),
+                "smart_indent" => self.smart_indent = config_throw_error!(
+                    value.as_bool(),
+                    panic_str
+                ),
+                "backup" => self.backup = config_throw_error!(
+                    value.as_bool(),
+                    panic_str
+                ),
+                "remember_position" => self.remember_position = config_throw_error!(
+                    value.as_bool(),
+                    panic_str
+                ),
+                "max_positions" => self.max_positions = config_throw_error!(
+                    value.as_integer().and_then(|value| usize::try_from(value).ok()),
+                    panic_str
+                ),
                 _ => return Err(
                     ErrorType::Specific(
                         format!("Unknow option: {}", prop)
@@ -0,0 +1,99 @@
+// Disk Stamp
+
+use std::fs::Metadata;
+use std::hash::{DefaultHasher, Hasher};
+use std::path::Path;
+use std::time::SystemTime;
+
+use tokio::fs;
+use tokio::io::{self, ErrorKind};
+
+/// The state of file on disk when it was loaded or saved, which is used to know
+/// whether the file has been changed by others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskStamp {
+    modified: Option<SystemTime>,
+    size: u64,
+
+    // The hash of content, which is None for large file.
+    hash: Option<u64>
+}
+
+impl DiskStamp {
+    pub fn new(metadata: &Metadata, bytes: Option<&[u8]>) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            hash: bytes.map(Self::hash)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Check whether the file at `path` has been changed since this stamp.
+    /// When only the modification time is changed, the stamp is updated with it.
+    pub async fn check(&mut self, path: &Path) -> io::Result<bool> {
+        let metadata = match fs::metadata(path).await {
+            Ok(metadata) => metadata,
+            // The deleted file will be created again when saving.
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+
+        if metadata.len() != self.size {
+            return Ok(true)
+        }
+
+        if metadata.modified().ok() == self.modified {
+            return Ok(false)
+        }
+
+        let hash = match self.hash {
+            Some(hash) => hash,
+            None => return Ok(true),
+        };
+
+        if Self::hash(&fs::read(path).await?) != hash {
+            return Ok(true)
+        }
+
+        self.modified = metadata.modified().ok();
+
+        Ok(false)
+    }
+
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(bytes);
+
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn check_test() {
+        let dir = TempDir::new("disk_stamp_test");
+        let path = dir.join("file.txt");
+        std::fs::write(&path, "content").unwrap();
+
+        let metadata = std::fs::metadata(&path).unwrap();
+        let mut stamp = DiskStamp::new(&metadata, Some(b"content"));
+        assert!(!stamp.check(&path).await.unwrap());
+
+        // Rewritten with the same content
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH).unwrap();
+        assert!(!stamp.check(&path).await.unwrap());
+
+        std::fs::write(&path, "changed").unwrap();
+        assert!(stamp.check(&path).await.unwrap());
+    }
+}
@@ -0,0 +1,407 @@
+// File Encoding
+
+use chardetng::EncodingDetector;
+use encoding_rs::{
+    DecoderResult,
+    EncoderResult,
+    Encoding,
+    UTF_16BE,
+    UTF_16LE,
+    UTF_8
+};
+
+/// The bytes which cannot be decoded are stored as chars in private use area,
+/// the code point of which equals to this value plus the byte.
+/// The real chars in this range are stored as the raw bytes of them.
+const RAW_BYTE_BASE: u32 = 0x10FF00;
+
+/// The size of content used to detect encoding.
+const DETECT_SIZE: usize = 64 * 1024;
+
+/// The character encoding of a file, and whether it starts with BOM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding {
+    encoding: &'static Encoding,
+    bom: bool
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self { encoding: UTF_8, bom: false }
+    }
+}
+
+impl FileEncoding {
+    /// Get encoding from its label, the label ending with " BOM" is for unicode with BOM.
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label.trim();
+        let (label, bom) = match label.rsplit_once(' ') {
+            Some((name, suffix)) if suffix.eq_ignore_ascii_case("bom") => (name.trim(), true),
+            _ => (label, false)
+        };
+
+        let encoding = Encoding::for_label(label.as_bytes())?;
+
+        if bom && ![UTF_8, UTF_16LE, UTF_16BE].contains(&encoding) {
+            return None
+        }
+
+        Some(Self { encoding, bom })
+    }
+
+    /// Detect encoding with BOM, or the content at the beginning of file.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            return Self { encoding, bom: true }
+        }
+
+        let sample = &bytes[..bytes.len().min(DETECT_SIZE)];
+
+        // ASCII chars in UTF-16 are valid UTF-8 as well
+        if let Some(encoding) = Self::detect_utf16(sample) {
+            return Self { encoding, bom: false }
+        }
+
+        if Self::mostly_utf8(sample) {
+            return Self::default()
+        }
+
+        let mut detector = EncodingDetector::new();
+        detector.feed(sample, sample.len() == bytes.len());
+
+        Self { encoding: detector.guess(None, false), bom: false }
+    }
+
+    pub fn name(&self) -> String {
+        if self.bom {
+            format!("{} BOM", self.encoding.name())
+        } else {
+            String::from(self.encoding.name())
+        }
+    }
+
+    /// Whether the bytes of ASCII chars (including '\n') are the same as ASCII.
+    pub fn is_ascii_compatible(&self) -> bool {
+        self.encoding.is_ascii_compatible()
+    }
+
+    /// Get the length of BOM at the beginning of bytes.
+    pub fn bom_len(&self, bytes: &[u8]) -> usize {
+        if !self.bom {
+            return 0
+        }
+
+        match Encoding::for_bom(bytes) {
+            Some((encoding, len)) if encoding == self.encoding => len,
+            _ => 0
+        }
+    }
+
+    /// Decode bytes without BOM into text.
+    /// The undecodable bytes are kept as raw byte chars, which will be restored when encoding.
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        if self.encoding == UTF_8 {
+            return self.decode_utf8(bytes)
+        }
+
+        let mut decoder = self.encoding.new_decoder_without_bom_handling();
+        let mut text = String::new();
+        let mut src = bytes;
+
+        loop {
+            text.reserve(
+                decoder.max_utf8_buffer_length_without_replacement(src.len())
+                    .unwrap_or(src.len())
+            );
+
+            let start = text.len();
+            let (result, read) = decoder.decode_to_string_without_replacement(src, &mut text, true);
+            self.escape_raw_range(&mut text, start);
+
+            match result {
+                DecoderResult::InputEmpty => break,
+                DecoderResult::OutputFull => (),
+                DecoderResult::Malformed(bad_len, extra) => {
+                    // The bytes consumed after the malformed sequence are decoded again by decoder.
+                    let end = read - extra as usize;
+                    let start = end.saturating_sub(bad_len as usize);
+                    for byte in src[start..end].iter() {
+                        text.push(Self::raw_byte_char(*byte));
+                    }
+                },
+            }
+
+            src = &src[read..];
+        }
+
+        text
+    }
+
+    /// Encode text with BOM into bytes.
+    /// Return the char which cannot be encoded when failed.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, char> {
+        let mut bytes = Vec::with_capacity(text.len());
+
+        if self.bom {
+            bytes.extend_from_slice(match self.encoding {
+                _encoding if _encoding == UTF_16LE => b"\xFF\xFE",
+                _encoding if _encoding == UTF_16BE => b"\xFE\xFF",
+                _ => b"\xEF\xBB\xBF",
+            });
+        }
+
+        // Encode the text between raw byte chars
+        let mut encoder = self.encoding.new_encoder();
+        let mut start = 0;
+
+        for (idx, _char) in text.char_indices() {
+            if let Some(byte) = Self::char_raw_byte(_char) {
+                self.encode_str(&mut encoder, &text[start..idx], &mut bytes, false)?;
+                bytes.push(byte);
+                start = idx + _char.len_utf8();
+            }
+        }
+
+        self.encode_str(&mut encoder, &text[start..], &mut bytes, true)?;
+
+        Ok(bytes)
+    }
+
+    fn encode_str(
+        &self,
+        encoder: &mut encoding_rs::Encoder,
+        text: &str,
+        bytes: &mut Vec<u8>,
+        last: bool
+    ) -> Result<(), char>
+    {
+        match self.encoding {
+            _encoding if _encoding == UTF_8 => bytes.extend_from_slice(text.as_bytes()),
+            _encoding if _encoding == UTF_16LE => {
+                text.encode_utf16().for_each(|unit| bytes.extend(unit.to_le_bytes()));
+            },
+            _encoding if _encoding == UTF_16BE => {
+                text.encode_utf16().for_each(|unit| bytes.extend(unit.to_be_bytes()));
+            },
+            _ => {
+                let mut src = text;
+
+                loop {
+                    bytes.reserve(
+                        encoder.max_buffer_length_from_utf8_without_replacement(src.len())
+                            .unwrap_or(src.len())
+                    );
+
+                    let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(
+                        src,
+                        bytes,
+                        last
+                    );
+                    src = &src[read..];
+
+                    match result {
+                        EncoderResult::InputEmpty => break,
+                        EncoderResult::OutputFull => (),
+                        EncoderResult::Unmappable(_char) => return Err(_char),
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_utf8(&self, bytes: &[u8]) -> String {
+        let mut text = String::with_capacity(bytes.len());
+        let mut src = bytes;
+
+        loop {
+            match std::str::from_utf8(src) {
+                Ok(valid) => {
+                    let start = text.len();
+                    text.push_str(valid);
+                    self.escape_raw_range(&mut text, start);
+                    break;
+                },
+                Err(err) => {
+                    let valid_len = err.valid_up_to();
+                    let bad_len = err.error_len().unwrap_or(src.len() - valid_len);
+
+                    let start = text.len();
+                    text.push_str(
+                        std::str::from_utf8(&src[..valid_len])
+                            .expect("Error code 1 at decode_utf8 in encoding.rs!")
+                    );
+                    self.escape_raw_range(&mut text, start);
+
+                    for byte in src[valid_len..(valid_len + bad_len)].iter() {
+                        text.push(Self::raw_byte_char(*byte));
+                    }
+
+                    src = &src[(valid_len + bad_len)..];
+                }
+            }
+        }
+
+        text
+    }
+
+    /// Replace the decoded chars after `start` which are within the range of raw byte chars
+    /// with the raw bytes of them, thus they're not mistaken for raw bytes when encoding.
+    fn escape_raw_range(&self, text: &mut String, start: usize) {
+        if !text[start..].chars().any(|_char| Self::char_raw_byte(_char).is_some()) {
+            return
+        }
+
+        let decoded = text.split_off(start);
+        let mut encoder = self.encoding.new_encoder();
+
+        for _char in decoded.chars() {
+            if Self::char_raw_byte(_char).is_none() {
+                text.push(_char);
+                continue
+            }
+
+            let mut bytes = Vec::new();
+            self.encode_str(&mut encoder, _char.encode_utf8(&mut [0; 4]), &mut bytes, false)
+                .expect("Error code 1 at escape_raw_range in encoding.rs!");
+
+            for byte in bytes {
+                text.push(Self::raw_byte_char(byte));
+            }
+        }
+    }
+
+    /// Whether the bytes are UTF-8 with only a few invalid sequences.
+    fn mostly_utf8(sample: &[u8]) -> bool {
+        let (mut invalid, mut multi_bytes) = (0, 0);
+        let mut src = sample;
+
+        while !src.is_empty() {
+            let (valid_len, bad_len) = match std::str::from_utf8(src) {
+                Ok(_) => (src.len(), 0),
+                Err(err) => match err.error_len() {
+                    Some(bad_len) => {
+                        invalid += 1;
+                        (err.valid_up_to(), bad_len)
+                    },
+                    // The sample may cut off the last char
+                    None => (err.valid_up_to(), src.len() - err.valid_up_to()),
+                },
+            };
+
+            multi_bytes += src[..valid_len].iter()
+                .filter(|byte| **byte >= 0xC0)
+                .count();
+
+            src = &src[(valid_len + bad_len)..];
+        }
+
+        invalid == 0 || multi_bytes > invalid * 4
+    }
+
+    /// Detect UTF-16 without BOM by the zero bytes of ASCII chars.
+    fn detect_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+        let half = sample.len() / 2;
+        if half == 0 {
+            return None
+        }
+
+        let (mut even_zero, mut odd_zero) = (0, 0);
+        for (idx, byte) in sample.iter().enumerate() {
+            if *byte == 0 {
+                if idx % 2 == 0 {
+                    even_zero += 1;
+                } else {
+                    odd_zero += 1;
+                }
+            }
+        }
+
+        if odd_zero * 5 > half * 2 && even_zero * 10 < half {
+            return Some(UTF_16LE)
+        }
+
+        if even_zero * 5 > half * 2 && odd_zero * 10 < half {
+            return Some(UTF_16BE)
+        }
+
+        None
+    }
+
+    fn raw_byte_char(byte: u8) -> char {
+        char::from_u32(RAW_BYTE_BASE + byte as u32)
+            .expect("Error code 1 at raw_byte_char in encoding.rs!")
+    }
+
+    fn char_raw_byte(_char: char) -> Option<u8> {
+        let code = _char as u32;
+
+        if code >= RAW_BYTE_BASE {
+            return Some((code - RAW_BYTE_BASE) as u8)
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        let gbk = FileEncoding::from_label("gbk").unwrap();
+        let bytes = gbk.encode("中文\n").unwrap();
+        assert_eq!(gbk.decode(&bytes), "中文\n");
+        assert!(gbk.encode("\u{1F600}").is_err());
+
+        // Invalid UTF-8 bytes are kept
+        let utf8 = FileEncoding::default();
+        let bytes = b"ok \xFF\xC3 \xE4\xB8\xAD\n";
+        let text = utf8.decode(bytes);
+        assert_eq!(text.chars().count(), 8);
+        assert_eq!(utf8.encode(&text).unwrap(), bytes);
+
+        // Invalid multibyte sequences are kept without losing the bytes after them
+        let bytes = b"\x810a\x82\n";
+        let text = gbk.decode(bytes);
+        assert_eq!(text.chars().skip(1).take(2).collect::<String>(), "0a");
+        assert_eq!(gbk.encode(&text).unwrap(), bytes);
+
+        // The chars within the range of raw byte chars are kept as well
+        let bytes = "a\u{10FF41}\u{10FFFF}\n".as_bytes();
+        assert_eq!(utf8.encode(&utf8.decode(bytes)).unwrap(), bytes);
+
+        let utf16 = FileEncoding::from_label("utf-16le").unwrap();
+        let bytes = "\u{10FF00}b".encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<_>>();
+        assert_eq!(utf16.encode(&utf16.decode(&bytes)).unwrap(), bytes);
+
+        let gb18030 = FileEncoding::from_label("gb18030").unwrap();
+        let bytes = encoding_rs::GB18030.encode("中\u{10FF80}").0.into_owned();
+        assert_eq!(gb18030.encode(&gb18030.decode(&bytes)).unwrap(), bytes);
+
+        let shift_jis = FileEncoding::from_label("shift_jis").unwrap();
+        let bytes = b"\x82 \x82\xA0\xFF\n";
+        assert_eq!(shift_jis.encode(&shift_jis.decode(bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn detect_test() {
+        let bytes = b"\xEF\xBB\xBFabc";
+        let encoding = FileEncoding::detect(bytes);
+        assert_eq!(encoding.name(), "UTF-8 BOM");
+        assert_eq!(encoding.bom_len(bytes), 3);
+        assert_eq!(encoding.encode("abc").unwrap(), bytes);
+
+        let utf16 = "plain text\n".encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(FileEncoding::detect(&utf16).name(), "UTF-16LE");
+
+        let latin1 = b"caf\xE9 cr\xE8me br\xFBl\xE9e, na\xEFve fa\xE7ade\n";
+        let encoding = FileEncoding::detect(latin1);
+        assert_eq!(encoding.name(), "windows-1252");
+        assert_eq!(encoding.decode(latin1), "café crème brûlée, naïve façade\n");
+    }
+}
@@ -1,40 +1,100 @@
 // File State
 
+use super::disk_stamp::DiskStamp;
+use super::encoding::FileEncoding;
+use super::hex_buffer::HexBuffer;
+use super::history::{Change, FileFormat, History};
+use super::line_ending::LineEnding;
+use super::save::write_file;
+use super::source::BufferSource;
+use super::swap::{SwapFile, SwapState};
+use super::text_buffer::TextBuffer;
+use super::transform::transform_text;
 use super::type_convert::{ColorConvert, StyleConvert};
+use crate::config::SaveTransform;
 use crate::error::{AppError, AppResult, ErrorType};
+use crate::utils::{
+    byte_to_grapheme,
+    changed_lines,
+    display_range,
+    grapheme_len,
+    grapheme_to_byte,
+    state_dir
+};
 
+use memmap2::Mmap;
 use ratatui::style::{Color, Style};
+use unicode_segmentation::UnicodeSegmentation;
 use tokio::{fs, sync::Mutex};
 use tokio::sync::mpsc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncReadExt, ErrorKind};
 
 use syntect::{
-    parsing::SyntaxSet,
+    parsing::{SyntaxReference, SyntaxSet},
     easy::HighlightLines,
     highlighting::{Theme, ThemeSet},
 };
 
 use std::sync::Arc;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+/// The file whose size is larger than this will be opened in large file mode.
+const LARGE_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
 pub type StylizedVec = Vec<ContentLine>;
-type LineVec = Vec<String>;
 type StylizedContent = Vec<(ratatui::style::Style, String)>;
 
 /// A structure storing single line of stylized content.
 #[derive(Debug, Clone)]
 pub struct ContentLine(StylizedContent);
 
-// TODO: Do not load all the file when the file is too large
+/// The file which buffer was bound to before `rebind`, it's restored when failed
+/// to save into the new file.
+#[derive(Debug)]
+struct FileBinding {
+    source: BufferSource,
+    syntax: Option<String>,
+    swap: Option<SwapFile>,
+    swap_checked: bool,
+    disk_stamp: Option<DiskStamp>,
+    disk_noticed: bool,
+    new_file: bool,
+    to_stdout: bool
+}
+
 #[derive(Debug)]
 pub struct FileState {
     pub background_color: Option<Color>,
-    content: Arc<Mutex<LineVec>>,
+    content: Arc<Mutex<TextBuffer>>,
     stylized: Arc<Mutex<StylizedVec>>,
 
     file_modified: Arc<Mutex<bool>>,
-
-    path: PathBuf,
+    history: History,
+
+    source: BufferSource,
+    syntax: Option<String>,
+    encoding: FileEncoding,
+    opened_encoding: Option<FileEncoding>,
+    line_ending: LineEnding,
+    final_newline: bool,
+    swap: Option<SwapFile>,
+    swap_checked: bool,         // The swap file is only checked once for each buffer
+
+    disk_stamp: Option<DiskStamp>,
+    disk_noticed: bool,
+    new_file: bool,
+    readonly: bool,
+
+    // The raw bytes edited in hex view, which are saved rather than the content.
+    hex: Option<HexBuffer>,
+
+    // Saving writes the content into stdout when exiting, rather than the file.
+    to_stdout: bool,
+    output: Option<Vec<u8>>,
+
+    // The previous file kept until the rebound file is saved.
+    previous_binding: Option<FileBinding>,
     theme: Theme,
     syntax_set: SyntaxSet
 }
@@ -49,8 +109,32 @@ impl Into<String> for ContentLine {
 }
 
 impl ContentLine {
-    pub fn get_iter<'a>(&'a self) -> impl Iterator<Item = &'a (Style, String)> {
-        self.0.iter()
+    /// Split the line into grapheme clusters with their styles.
+    /// A cluster crossing several styled spans uses the style of its first char.
+    pub fn graphemes(&self) -> Vec<(Style, String)> {
+        let text = self.0.iter()
+            .map(|(_, span)| span.as_str())
+            .collect::<String>();
+
+        let mut spans = self.0.iter();
+        let mut style = Style::default();
+        let mut span_end = 0;
+
+        text.grapheme_indices(true)
+            .map(|(idx, grapheme)| {
+                while idx >= span_end {
+                    match spans.next() {
+                        Some((_style, span)) => {
+                            style = *_style;
+                            span_end += span.len();
+                        },
+                        None => break,
+                    }
+                }
+
+                (style, String::from(grapheme))
+            })
+            .collect()
     }
 }
 
@@ -64,7 +148,7 @@ impl FileState {
         *self.file_modified.lock().await
     }
 
-    pub fn content_ref(&self) -> &Arc<Mutex<LineVec>> {
+    pub fn content_ref(&self) -> &Arc<Mutex<TextBuffer>> {
         &self.content
     }
 
@@ -72,37 +156,474 @@ impl FileState {
         &self.stylized
     }
 
-    pub async fn init(&mut self, path: String) -> AppResult<()> {
-        let path = Self::get_absolute(path);
+    pub fn encoding(&self) -> FileEncoding {
+        self.encoding
+    }
+
+    /// Change the encoding used for saving file.
+    pub async fn set_encoding(&mut self, encoding: FileEncoding) {
+        if encoding == self.encoding {
+            return
+        }
+
+        // The mapped content has to be converted when saving.
+        self.content.lock().await.materialize();
+        self.encoding = encoding;
+
+        self.history.unmark_saved();
+        self.file_modify().await;
+    }
+
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Whether the file ends with a line break.
+    pub fn final_newline(&self) -> bool {
+        self.final_newline
+    }
+
+    fn format(&self) -> FileFormat {
+        FileFormat { line_ending: self.line_ending, final_newline: self.final_newline }
+    }
+
+    fn set_format(&mut self, format: FileFormat) {
+        self.line_ending = format.line_ending;
+        self.final_newline = format.final_newline;
+    }
+
+    /// Convert the line ending used for saving file between LF and CRLF.
+    pub async fn switch_line_ending(&mut self) {
+        // The mapped content has to be converted when saving.
+        self.content.lock().await.materialize();
+        self.line_ending = self.line_ending.switch();
 
-        let file = fs::File::open(path.to_owned()).await?;
+        self.history.unmark_saved();
+        self.file_modify().await;
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.source.path()
+    }
+
+    pub fn name(&self) -> String {
+        self.source.name()
+    }
+
+    pub fn hex(&self) -> Option<&HexBuffer> {
+        self.hex.as_ref()
+    }
+
+    /// Switch between the text & the hex view of content, return the cursor position
+    /// in new view, which is at the beginning of the line at `cursor`.
+    pub async fn toggle_hex(&mut self, cursor: (usize, usize)) -> AppResult<(usize, usize)> {
+        let hex = match self.hex.take() {
+            Some(hex) => hex,
+            None => {
+                let bytes = self.encode_content().await?;
+                let lines = self.content.lock().await.lines()
+                    .take(cursor.1)
+                    .collect::<String>();
+
+                // The lines before cursor are encoded as they're saved, BOM is included.
+                let offset = self.encoding.encode(&self.line_ending.apply(lines, true))
+                    .map_or(0, |prefix| prefix.len());
+
+                self.hex = Some(HexBuffer::new(bytes));
+
+                return Ok((offset % HexBuffer::ROW_SIZE, offset / HexBuffer::ROW_SIZE))
+            },
+        };
+
+        let offset = (cursor.1 * HexBuffer::ROW_SIZE + cursor.0).min(hex.len());
+        let bom_len = self.encoding.bom_len(hex.bytes());
+        let line = self.encoding.decode(&hex.bytes()[bom_len..offset.max(bom_len)])
+            .matches('\n')
+            .count();
+
+        if !hex.edited() {
+            return Ok((0, line.min(self.content.lock().await.len() - 1)))
+        }
+
+        // The edited bytes replace the content, which can be undone.
+        let (buffer, _, line_ending, final_newline) = Self::decode_content(
+            hex.bytes(),
+            Some(self.encoding)
+        );
+        let text = buffer.chunks().collect::<String>();
+        let format = self.format();
+        (self.line_ending, self.final_newline) = (line_ending, final_newline);
+
+        let mut file_lines = self.content.lock().await;
+        file_lines.materialize();
+
+        // The undo step starts with the cursor in text view.
+        self.history.commit(cursor);
+        self.history.begin((0, line.min(file_lines.len() - 1)));
+
+        let end = file_lines.line_to_char(file_lines.len());
+        let change = Self::replace_range(&mut file_lines, 0..end, text);
+        self.history.record(change);
+        self.history.record_format(format, self.format());
+
+        Ok((0, line.min(file_lines.len() - 1)))
+    }
+
+    /// Overwrite the byte at `idx` in hex view.
+    pub async fn set_hex_byte(&mut self, idx: usize, byte: u8) {
+        if self.hex.as_mut().is_some_and(|hex| hex.set_byte(idx, byte)) {
+            self.file_modify().await;
+        }
+    }
+
+    /// Overwrite the high or low 4 bits of the byte at `idx` in hex view.
+    pub async fn set_hex_nibble(&mut self, idx: usize, low: bool, value: u8) {
+        if self.hex.as_mut().is_some_and(|hex| hex.set_nibble(idx, low, value)) {
+            self.file_modify().await;
+        }
+    }
+
+    /// Open the file at `path`, or read from stdin when it's "-".
+    /// The encoding will be detected when it's not specified.
+    pub async fn init(&mut self, path: String, encoding: Option<FileEncoding>) -> AppResult<()> {
+        self.source = if path == "-" {
+            BufferSource::Stdin
+        } else {
+            let path = Self::get_absolute(path);
+            self.swap = Some(SwapFile::new(&state_dir().join("swap"), &path));
+
+            BufferSource::File(path)
+        };
+        self.opened_encoding = encoding;
+
+        self.load().await
+    }
+
+    /// Highlight the content with the syntax named `name`, rather than the detected one.
+    pub fn set_syntax(&mut self, name: String) -> AppResult<()> {
+        if self.syntax_set.find_syntax_by_token(&name).is_none() {
+            return Err(
+                ErrorType::Specific(format!("Unknown syntax: {}", name)).pack()
+            )
+        }
+
+        self.syntax = Some(name);
+
+        Ok(())
+    }
+
+    /// Make saving write the content into stdout when exiting.
+    pub fn write_to_stdout(&mut self) {
+        self.to_stdout = true;
+    }
+
+    /// Take the saved content to be written into stdout.
+    pub fn take_output(&mut self) -> Option<Vec<u8>> {
+        self.output.take()
+    }
+
+    /// Read the file into content, which discards all the changes.
+    async fn load(&mut self) -> AppResult<()> {
+        let encoding = self.opened_encoding;
+        let path = match self.source {
+            BufferSource::File(ref path) => path.to_owned(),
+            BufferSource::Stdin => return self.load_stdin().await,
+        };
+
+        let mut file = match fs::File::open(path).await {
+            Ok(file) => file,
+            // The missing file will be created when saving.
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                *self.content.lock().await = TextBuffer::from_text("\n");
+                self.encoding = encoding.unwrap_or_default();
+                self.line_ending = LineEnding::default();
+                self.final_newline = true;
+                self.disk_stamp = None;
+                self.new_file = true;
+                self.hex = None;
+
+                self.reset_history().await;
+
+                return Ok(())
+            },
+            Err(err) => return Err(err.into()),
+        };
         let content_ref = Arc::clone(&self.content);
 
         let read_result = tokio::join!(async move {
-            let mut content = content_ref.lock().await;
-            let mut reader_lines = BufReader::new(file).lines();
+            let metadata = file.metadata().await?;
+
+            // Large file is mapped into memory rather than being read.
+            if metadata.len() >= LARGE_FILE_SIZE {
+                let file = file.into_std().await;
+                // SAFETY: The mapped file is only read, and it will be dropped
+                // once the content is edited.
+                let mmap = unsafe { Mmap::map(&file)? };
+                // Hex view would copy the whole file, thus it's only opened by user.
+                let hex = None;
+                let encoding = encoding.unwrap_or_else(|| FileEncoding::detect(&mmap));
+                let stamp = DiskStamp::new(&metadata, None);
+
+                // The line breaks cannot be found directly within other encodings.
+                if encoding.is_ascii_compatible() {
+                    let line_ending = LineEnding::detect(&mmap);
+                    let final_newline = mmap.last() == Some(&b'\n');
+                    *content_ref.lock().await = TextBuffer::from_mmap(mmap, encoding);
+
+                    return Ok((encoding, line_ending, final_newline, stamp, hex))
+                }
+
+                let text = encoding.decode(&mmap[encoding.bom_len(&mmap)..]);
+                *content_ref.lock().await = TextBuffer::from_text(&text);
 
-            while let Some(mut line) = reader_lines.next_line().await? {
-                // line = strip_ansi_escapes::strip_str(&line);
-                line.push('\n');
-                content.push(line.to_owned());
+                return Ok((
+                    encoding,
+                    LineEnding::detect(text.as_bytes()),
+                    text.ends_with('\n'),
+                    stamp,
+                    hex
+                ))
             }
 
-            Ok::<(), AppError>(())
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes).await?;
+
+            let hex = Self::detect_binary(&bytes, encoding);
+            let (buffer, encoding, line_ending, final_newline) = Self::decode_content(&bytes, encoding);
+            *content_ref.lock().await = buffer;
+            let stamp = DiskStamp::new(&metadata, Some(&bytes));
+
+            Ok::<_, AppError>((encoding, line_ending, final_newline, stamp, hex))
         });
 
-        read_result.0?;
-        self.path = path;
+        let stamp;
+        (self.encoding, self.line_ending, self.final_newline, stamp, self.hex) = read_result.0?;
+        self.disk_stamp = Some(stamp);
+        self.disk_noticed = false;
+        self.new_file = false;
+
+        self.reset_history().await;
+
+        Ok(())
+    }
+
+    /// Read the whole stdin into content, which can only be done once.
+    async fn load_stdin(&mut self) -> AppResult<()> {
+        let mut bytes = Vec::new();
+        tokio::io::stdin().read_to_end(&mut bytes).await?;
+
+        let buffer;
+        self.hex = Self::detect_binary(&bytes, self.opened_encoding);
+        (buffer, self.encoding, self.line_ending, self.final_newline) = Self::decode_content(
+            &bytes,
+            self.opened_encoding
+        );
+        *self.content.lock().await = buffer;
+
+        self.disk_stamp = None;
+        self.new_file = false;
+
+        self.reset_history().await;
+
+        Ok(())
+    }
+
+    /// Decode the whole content of file, return it with its encoding, line ending
+    /// & whether it has final newline.
+    fn decode_content(
+        bytes: &[u8],
+        encoding: Option<FileEncoding>
+    ) -> (TextBuffer, FileEncoding, LineEnding, bool)
+    {
+        let encoding = encoding.unwrap_or_else(|| FileEncoding::detect(bytes));
+        let text = encoding.decode(&bytes[encoding.bom_len(bytes)..]);
+
+        // text = strip_ansi_escapes::strip_str(&text);
+        // Empty file is loaded as an empty line, thus it can be edited.
+        let buffer = TextBuffer::from_text(if text.is_empty() { "\n" } else { &text });
+
+        // Empty file is regarded as having final newline, which will be added after editing.
+        let final_newline = text.is_empty() || text.ends_with('\n');
+
+        (buffer, encoding, LineEnding::detect(text.as_bytes()), final_newline)
+    }
+
+    /// Open binary content in hex view, unless the encoding is specified by user.
+    fn detect_binary(bytes: &[u8], encoding: Option<FileEncoding>) -> Option<HexBuffer> {
+        if encoding.is_none() && HexBuffer::is_binary(bytes) {
+            return Some(HexBuffer::new(bytes.to_vec()))
+        }
+
+        None
+    }
+
+    async fn reset_history(&mut self) {
+        self.history = History::default();
+        self.history.mark_saved();
+        *self.file_modified.lock().await = false;
+    }
+
+    /// Whether the file doesn't exist on disk until it's saved.
+    pub fn is_new_file(&self) -> bool {
+        self.new_file
+    }
+
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// Forbid or allow modifying the content & saving it.
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly;
+    }
+
+    /// Whether the file can be written by current user, the missing file is regarded as writable.
+    pub async fn writable(&self) -> bool {
+        let path = match self.source.path() {
+            Some(path) => path,
+            None => return true,
+        };
+
+        match fs::OpenOptions::new().write(true).open(path).await {
+            Ok(_) => true,
+            Err(err) => err.kind() == ErrorKind::NotFound,
+        }
+    }
+
+    /// Get the parent directory of file if it doesn't exist.
+    pub async fn missing_dir(&self) -> Option<PathBuf> {
+        // The content written into stdout doesn't need a directory.
+        if self.to_stdout {
+            return None
+        }
+
+        let parent = self.source.path()?.parent()?;
+
+        match fs::try_exists(parent).await {
+            Ok(false) => Some(parent.to_path_buf()),
+            _ => None
+        }
+    }
+
+    /// Create the missing parent directories of file.
+    pub async fn create_dir(&self) -> AppResult<()> {
+        if let Some(parent) = self.source.path().and_then(Path::parent) {
+            fs::create_dir_all(parent).await?;
+        }
 
         Ok(())
     }
 
+    /// Read the file again, discarding the changes of content.
+    pub async fn reload(&mut self) -> AppResult<()> {
+        if self.source == BufferSource::Stdin {
+            return Err(
+                ErrorType::Specific(String::from("Cannot reload the content read from stdin")).pack()
+            )
+        }
+
+        self.load().await
+    }
+
+    /// Bind the buffer to the file at `path`, which is written by the next saving.
+    /// The syntax will be detected again with the new path.
+    /// The previous file is kept until saved, thus it can be restored by `restore_binding`.
+    pub async fn rebind(&mut self, path: PathBuf) {
+        // The mapped content cannot be kept after leaving its file.
+        self.content.lock().await.materialize();
+
+        // The file rebound without saving is dropped, the first one is restored.
+        if self.previous_binding.is_some() {
+            self.remove_swap().await;
+        }
+
+        let new_file = !fs::try_exists(&path).await.unwrap_or(false);
+        let swap = Some(SwapFile::new(&state_dir().join("swap"), &path));
+
+        let binding = FileBinding {
+            source: std::mem::replace(&mut self.source, BufferSource::File(path)),
+            syntax: self.syntax.take(),
+            swap: std::mem::replace(&mut self.swap, swap),
+            swap_checked: std::mem::replace(&mut self.swap_checked, true),
+            disk_stamp: self.disk_stamp.take(),
+            disk_noticed: std::mem::replace(&mut self.disk_noticed, false),
+            new_file: std::mem::replace(&mut self.new_file, new_file),
+            to_stdout: std::mem::replace(&mut self.to_stdout, false)
+        };
+
+        if self.previous_binding.is_none() {
+            self.previous_binding = Some(binding);
+        }
+    }
+
+    /// Bind the buffer to the file before `rebind` again, when the rebound file is not saved.
+    pub async fn restore_binding(&mut self) {
+        let binding = match self.previous_binding.take() {
+            Some(binding) => binding,
+            None => return,
+        };
+
+        self.remove_swap().await;
+
+        self.source = binding.source;
+        self.syntax = binding.syntax;
+        self.swap = binding.swap;
+        self.swap_checked = binding.swap_checked;
+        self.disk_stamp = binding.disk_stamp;
+        self.disk_noticed = binding.disk_noticed;
+        self.new_file = binding.new_file;
+        self.to_stdout = binding.to_stdout;
+    }
+
+    /// Check whether the file has been changed by others since it was loaded or saved.
+    pub async fn disk_changed(&mut self) -> AppResult<bool> {
+        match (&mut self.disk_stamp, self.source.path()) {
+            (Some(stamp), Some(path)) => Ok(stamp.check(path).await?),
+            _ => Ok(false),
+        }
+    }
+
+    /// Same as `disk_changed`, but the change is only reported once until the file
+    /// is loaded or saved again.
+    pub async fn notice_disk_change(&mut self) -> AppResult<bool> {
+        if self.disk_noticed || !self.disk_changed().await? {
+            return Ok(false)
+        }
+
+        self.disk_noticed = true;
+
+        Ok(true)
+    }
+
+    /// Get the indexes of lines in content which are different from the file on disk.
+    pub async fn diff_disk(&self) -> AppResult<Vec<usize>> {
+        let bytes = match self.source.path() {
+            Some(path) => fs::read(path).await?,
+            None => Vec::new(),
+        };
+        let text = self.encoding.decode(&bytes[self.encoding.bom_len(&bytes)..]);
+        let disk_lines = text.lines()
+            .map(|line| format!("{}\n", line))
+            .collect::<Vec<_>>();
+
+        let content = self.content.lock().await;
+        let lines = content.lines()
+            .map(|line| line.into_owned())
+            .collect::<Vec<_>>();
+
+        Ok(changed_lines(
+            &disk_lines.iter().map(String::as_str).collect::<Vec<_>>(),
+            &lines.iter().map(String::as_str).collect::<Vec<_>>()
+        ))
+    }
+
     pub async fn refresh_stylized(
         &mut self,
         start: usize,
         height: usize,
     ) -> AppResult<()> {
         let content = self.content.lock().await;
+        let syntax = self.find_syntax(&content)?;
 
         let end = if start + height > content.len() {
             content.len()
@@ -113,7 +634,7 @@ impl FileState {
         // Parse content
         let (tx, rx) = mpsc::unbounded_channel();
         let sender_task = async move {
-            for line in content[start..end].iter() {
+            for line in content.lines_at(start).take(end - start) {
                 if tx.is_closed() {
                     return Err(
                         ErrorType::Specific(
@@ -122,7 +643,7 @@ impl FileState {
                     )
                 }
 
-                tx.send(line.to_owned()).unwrap();
+                tx.send(line.into_owned()).unwrap();
             }
 
             Ok::<(), AppError>(())
@@ -130,7 +651,7 @@ impl FileState {
 
         let (sender_result, parse_result) = tokio::join!(
             sender_task,
-            self.parse_content(self.path.to_owned(), rx)
+            self.parse_content(syntax, rx)
         );
 
         sender_result?;
@@ -148,17 +669,45 @@ impl FileState {
         Ok(())
     }
 
+    /// Get the name of syntax used to highlight the content.
+    pub async fn syntax_name(&self) -> Option<String> {
+        let content = self.content.lock().await;
+
+        self.find_syntax(&content).ok()?
+            .map(|syntax| syntax.name.to_owned())
+    }
+
     // TODO: Use string as parameter rather than channel when there's no need
-    async fn parse_content<'a, P>(
+    /// Find the syntax specified by user, or detect it with the file path.
+    /// The content from stdin is detected with its first line.
+    fn find_syntax(&self, content: &TextBuffer) -> AppResult<Option<&SyntaxReference>> {
+        if let Some(ref name) = self.syntax {
+            return Ok(self.syntax_set.find_syntax_by_token(name))
+        }
+
+        let path = match self.source.path() {
+            Some(path) => path,
+            None if content.is_empty() => return Ok(None),
+            None => return Ok(self.syntax_set.find_syntax_by_first_line(&content.line(0))),
+        };
+
+        match self.syntax_set.find_syntax_for_file(path) {
+            Ok(syntax) => Ok(syntax),
+            // The syntax of new file can only be found by its extension,
+            // and the first line of binary file cannot be read as text.
+            Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::InvalidData) => {
+                Ok(None)
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn parse_content(
         &self,
-        path: P,
+        find_syntax: Option<&SyntaxReference>,
         mut rx: mpsc::UnboundedReceiver<String>,
     ) -> AppResult<(StylizedVec, Color)>
-    where P: AsRef<Path>
     {
-        let find_syntax = self.syntax_set.find_syntax_for_file(
-            path.as_ref()
-        )?;
 
         let mut result: StylizedVec = Vec::new();
         let mut get_bg = false;
@@ -211,8 +760,7 @@ impl FileState {
     }
 
     /// Get lines from file content with range.
-    pub async fn get_lines(&self, from: u16, to: u16) -> AppResult<Vec<String>> {
-        let (from, to) = (from as usize, to as usize);
+    pub async fn get_lines(&self, from: usize, to: usize) -> AppResult<Vec<String>> {
         let file_lines = self.content.lock().await;
 
         if file_lines.is_empty() {
@@ -231,20 +779,53 @@ impl FileState {
             )
         }
 
-        Ok(file_lines[from..=to].to_owned())
+        Ok((from..=to).map(|idx| file_lines.line(idx).into_owned()).collect())
+    }
+
+    /// Get the text between two cursor positions, the `to` position is exclusive.
+    pub async fn get_text(&self, from: (usize, usize), to: (usize, usize)) -> AppResult<String> {
+        let file_lines = self.content.lock().await;
+
+        // The mapped content is read by lines rather than being loaded.
+        if file_lines.is_mapped() {
+            return Self::text_by_lines(&file_lines, from, to)
+        }
+
+        let range = Self::pos_to_char(&file_lines, from)?..Self::pos_to_char(&file_lines, to)?;
+
+        if range.start > range.end {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Attempt to get text with wrong range.")
+                ).pack()
+            )
+        }
+
+        Ok(file_lines.slice(range))
+    }
+
+    /// Get the range of display columns taken by the grapheme at cursor.
+    pub async fn display_range(&self, cursor: (usize, usize), tab_width: usize) -> Range<usize> {
+        let file_lines = self.content.lock().await;
+
+        if cursor.1 >= file_lines.len() {
+            return 0..1
+        }
+
+        display_range(&file_lines.line(cursor.1), cursor.0, tab_width)
     }
 
     /// Modify lines with modified lines & range.
     /// Update its syntax highlight in the meanwhile.
     pub async fn modify_lines(
         &mut self,
-        from: u16,
-        to: u16,
+        from: usize,
+        to: usize,
         lines: Vec<String>
     ) -> AppResult<()>
     {
-        let (from, to) = (from as usize, to as usize);
         let mut file_lines = self.content.lock().await;
+        file_lines.materialize();
 
         if from > to || to >= file_lines.len() {
             return Err(
@@ -254,29 +835,443 @@ impl FileState {
             )
         }
 
-        file_lines.splice(from..=to, lines);
+        let range = file_lines.line_to_char(from)..file_lines.line_to_char(to + 1);
+        let change = Self::replace_range(&mut file_lines, range, lines.concat());
+        self.history.record(change);
+
+        self.file_modify().await;
+
+        Ok(())
+    }
+
+    /// Insert lines before the line at `at`.
+    /// When `at` equals to the length of content, the lines will be appended.
+    pub async fn insert_lines(&mut self, at: usize, lines: Vec<String>) -> AppResult<()> {
+        let mut file_lines = self.content.lock().await;
+        file_lines.materialize();
+
+        if at > file_lines.len() {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Attempt to insert lines with wrong position.")
+                ).pack()
+            )
+        }
+
+        let char_idx = file_lines.line_to_char(at);
+        let change = Self::replace_range(&mut file_lines, char_idx..char_idx, lines.concat());
+        self.history.record(change);
 
         self.file_modify().await;
 
         Ok(())
     }
 
-    pub async fn save_content(&mut self) -> AppResult<()> {
-        let mut file = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(self.path.to_owned()).await?;
+    /// Replace the text between two cursor positions, the `to` position is exclusive.
+    /// Return the replaced text.
+    pub async fn replace_text(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        text: &str
+    ) -> AppResult<String>
+    {
+        Ok(self.edit_text(from, to, text).await?.0)
+    }
+
+    /// Insert text at the position, return the position after the inserted text.
+    pub async fn insert_text(&mut self, pos: (usize, usize), text: &str) -> AppResult<(usize, usize)> {
+        Ok(self.edit_text(pos, pos, text).await?.1)
+    }
+
+    pub async fn remove_text(&mut self, from: (usize, usize), to: (usize, usize)) -> AppResult<String> {
+        self.replace_text(from, to, "").await
+    }
+
+    /// Return the replaced text & the position after the new text.
+    async fn edit_text(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+        text: &str
+    ) -> AppResult<(String, (usize, usize))>
+    {
+        let mut file_lines = self.content.lock().await;
+        file_lines.materialize();
+
+        let range = Self::pos_to_char(&file_lines, from)?..Self::pos_to_char(&file_lines, to)?;
+
+        if range.start > range.end {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Attempt to modify text with wrong range.")
+                ).pack()
+            )
+        }
+
+        let text_end = range.start + text.chars().count();
+        let change = Self::replace_range(&mut file_lines, range, String::from(text));
+        let removed = change.removed.to_owned();
+        let pos_after = Self::char_to_pos(&file_lines, text_end);
+
+        self.history.record(change);
+        self.file_modify().await;
+
+        Ok((removed, pos_after))
+    }
+
+    /// Convert cursor position into char index of the content.
+    /// The horizontal position of cursor is the index of grapheme cluster.
+    fn pos_to_char(file_lines: &TextBuffer, pos: (usize, usize)) -> AppResult<usize> {
+        let (x, y) = pos;
+
+        if file_lines.is_empty() {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Cannot execute current editing operation as it's a empty file!")
+                ).pack()
+            )
+        }
+
+        // The position after the last char of a line equals to the beginning of next line.
+        if y >= file_lines.len() {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Attempt to edit text at wrong position.")
+                ).pack()
+            )
+        }
+
+        let line = file_lines.line(y);
+        if x > grapheme_len(&line) {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Attempt to edit text at wrong position.")
+                ).pack()
+            )
+        }
+
+        let byte_idx = grapheme_to_byte(&line, x);
+
+        Ok(file_lines.line_to_char(y) + line[..byte_idx].chars().count())
+    }
+
+    fn text_by_lines(
+        file_lines: &TextBuffer,
+        from: (usize, usize),
+        to: (usize, usize)
+    ) -> AppResult<String>
+    {
+        if (from.1, from.0) > (to.1, to.0) || to.1 >= file_lines.len() {
+            return Err(
+                ErrorType::Specific(
+                    String::from("Attempt to get text with wrong range.")
+                ).pack()
+            )
+        }
+
+        let mut text = String::new();
+
+        for (y, line) in (from.1..=to.1).zip(file_lines.lines_at(from.1)) {
+            let start = if y == from.1 { from.0 } else { 0 };
+            let end = if y == to.1 { to.0 } else { usize::MAX };
+
+            text.extend(line.graphemes(true).take(end).skip(start));
+        }
+
+        Ok(text)
+    }
+
+    fn char_to_pos(file_lines: &TextBuffer, char_idx: usize) -> (usize, usize) {
+        let y = file_lines.char_to_line(char_idx);
+        let line = file_lines.line(y);
+        let byte_idx = line.char_indices()
+            .nth(char_idx - file_lines.line_to_char(y))
+            .map(|(idx, _)| idx)
+            .unwrap_or(line.len());
+
+        (byte_to_grapheme(&line, byte_idx, true), y)
+    }
+
+    fn replace_range(file_lines: &mut TextBuffer, range: Range<usize>, text: String) -> Change {
+        let at = range.start;
+        let removed = file_lines.remove(range);
+        file_lines.insert(at, &text);
+
+        Change { at, removed, inserted: text }
+    }
+
+    /// Start recording a new undo step if there's no pending one.
+    pub fn begin_step(&mut self, cursor: (usize, usize)) {
+        self.history.begin(cursor);
+    }
+
+    /// Finish current undo step.
+    pub fn commit_step(&mut self, cursor: (usize, usize)) {
+        self.history.commit(cursor);
+    }
+
+    /// Undo the latest step, return the cursor position before it.
+    pub async fn undo(&mut self) -> Option<(usize, usize)> {
+        let step = self.history.undo()?;
+        let mut file_lines = self.content.lock().await;
+
+        for change in step.changes.iter().rev() {
+            Self::apply_change(&mut file_lines, change.invert());
+        }
+        drop(file_lines);
+
+        if let Some((before, _)) = step.format {
+            self.set_format(before);
+        }
+
+        *self.file_modified.lock().await = !self.history.at_saved();
+
+        Some(step.cursor_before)
+    }
+
+    /// Redo the latest undone step, return the cursor position after it.
+    pub async fn redo(&mut self) -> Option<(usize, usize)> {
+        let step = self.history.redo()?;
+        let mut file_lines = self.content.lock().await;
+
+        for change in step.changes.into_iter() {
+            Self::apply_change(&mut file_lines, change);
+        }
+        drop(file_lines);
+
+        if let Some((_, after)) = step.format {
+            self.set_format(after);
+        }
+
+        *self.file_modified.lock().await = !self.history.at_saved();
+
+        Some(step.cursor_after)
+    }
+
+    fn apply_change(file_lines: &mut TextBuffer, change: Change) {
+        let end = change.at + change.removed.chars().count();
+        Self::replace_range(file_lines, change.at..end, change.inserted);
+    }
+
+    /// Save the content into file, keeping the previous version when `backup` is true.
+    pub async fn save_content(&mut self, backup: bool) -> AppResult<()> {
+        // The mapped content has not been edited, which is the same as the file.
+        // Besides, truncating a mapped file is unsafe.
+        if !self.to_stdout &&
+            self.content.lock().await.is_mapped() &&
+            self.hex.as_ref().is_none_or(|hex| !hex.edited())
+        {
+            *self.file_modified.lock().await = false;
+            return Ok(())
+        }
+
+        let bytes = match self.hex {
+            // The bytes edited in hex view are written without any conversion.
+            Some(ref hex) => {
+                self.content.lock().await.materialize();
+                hex.bytes().to_vec()
+            },
+            None => self.encode_content().await?,
+        };
+
+        let path = match self.source {
+            _ if self.to_stdout => {
+                self.output = Some(bytes);
+
+                *self.file_modified.lock().await = false;
+                self.history.mark_saved();
+
+                return Ok(())
+            },
+            BufferSource::File(ref path) => path,
+            BufferSource::Stdin => return Err(
+                ErrorType::Specific(
+                    String::from("The content read from stdin can only be saved with --stdout")
+                ).pack()
+            ),
+        };
+
+        write_file(path, &bytes, backup).await?;
+
+        let metadata = fs::metadata(path).await?;
+        self.disk_stamp = Some(DiskStamp::new(&metadata, Some(&bytes)));
+        self.disk_noticed = false;
+        self.new_file = false;
 
-        for line in self.content.lock().await.iter() {
-            file.write(line.as_bytes()).await?;
+        // The swap file of previous file is useless after saving into the rebound one.
+        if let Some(swap) = self.previous_binding.take().and_then(|binding| binding.swap) {
+            swap.remove().await;
         }
 
         *self.file_modified.lock().await = false;
+        self.history.mark_saved();
 
         Ok(())
     }
 
-    fn get_absolute(mut path: String) -> PathBuf {
+    /// Apply the transformations before saving, which can be undone.
+    /// The large file & the bytes edited in hex view are not transformed.
+    /// Return whether the content is changed.
+    pub async fn transform_content(
+        &mut self,
+        transform: &SaveTransform,
+        tab_indent: bool,
+        tab_width: usize
+    ) -> bool
+    {
+        if transform.is_empty() || self.hex.is_some() || self.content.lock().await.is_mapped() {
+            return false
+        }
+
+        if transform.final_newline {
+            self.final_newline = true;
+        }
+
+        let mut file_lines = self.content.lock().await;
+        let text = file_lines.chunks().collect::<String>();
+        let transformed = transform_text(&text, transform, tab_indent, tab_width);
+
+        if transformed == text {
+            return false
+        }
+
+        // Only the different part is replaced, thus the recorded change is small.
+        let (text_len, transformed_len) = (text.chars().count(), transformed.chars().count());
+        let prefix = text.chars()
+            .zip(transformed.chars())
+            .take_while(|(char_a, char_b)| char_a == char_b)
+            .count();
+        let suffix = text.chars().rev()
+            .zip(transformed.chars().rev())
+            .take_while(|(char_a, char_b)| char_a == char_b)
+            .count()
+            .min(text_len - prefix)
+            .min(transformed_len - prefix);
+
+        let inserted = transformed.chars()
+            .skip(prefix)
+            .take(transformed_len - prefix - suffix)
+            .collect::<String>();
+        let change = Self::replace_range(&mut file_lines, prefix..(text_len - suffix), inserted);
+        self.history.record(change);
+        drop(file_lines);
+
+        self.file_modify().await;
+
+        true
+    }
+
+    /// Convert the content into the bytes to be saved.
+    async fn encode_content(&self) -> AppResult<Vec<u8>> {
+        let text = self.content.lock().await.chunks().collect::<String>();
+
+        // The empty line of an empty or new file is not written.
+        let text = if text == "\n" && self.disk_stamp.as_ref().is_none_or(DiskStamp::is_empty) {
+            String::new()
+        } else {
+            self.line_ending.apply(text, self.final_newline)
+        };
+
+        self.encoding.encode(&text).map_err(|_char| {
+            ErrorType::Specific(
+                format!("Cannot encode character '{}' with {}", _char, self.encoding.name())
+            ).pack()
+        })
+    }
+
+    /// Check the swap file left by others.
+    /// The swap file used by another instance will not be touched by current one.
+    pub async fn check_swap(&mut self) -> AppResult<SwapState> {
+        let swap = match self.swap {
+            Some(ref mut swap) if !self.swap_checked => swap,
+            _ => return Ok(SwapState::None),
+        };
+        self.swap_checked = true;
+
+        let state = swap.check().await?;
+        if let SwapState::InUse(_) = state {
+            self.swap = None;
+        }
+
+        Ok(state)
+    }
+
+    /// Write the unsaved content into swap file when it's changed.
+    pub async fn write_swap(&mut self) -> AppResult<()> {
+        let swap = match self.swap {
+            // The bytes edited in hex view cannot be kept as text.
+            Some(_) if self.hex.is_some() => return Ok(()),
+            Some(ref mut swap) => swap,
+            None => return Ok(()),
+        };
+
+        let modified = *self.file_modified.lock().await;
+        let file_lines = self.content.lock().await;
+        let revision = file_lines.revision();
+
+        if !swap.need_write(revision, modified) {
+            return Ok(())
+        }
+
+        let content = if modified {
+            Some(file_lines.chunks().collect::<String>())
+        } else {
+            None
+        };
+        drop(file_lines);
+
+        // Stop writing swap file after failure, thus the error won't be shown repeatedly.
+        if let Err(err) = swap.write(revision, content).await {
+            self.swap = None;
+            return Err(err)
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove_swap(&mut self) {
+        if let Some(swap) = self.swap.take() {
+            swap.remove().await;
+        }
+    }
+
+    /// Replace the content with the one in swap file, which can be undone.
+    /// Return the indexes of changed lines.
+    pub async fn recover_swap(&mut self) -> Vec<usize> {
+        let recovery = match self.swap.as_mut().and_then(|swap| swap.take_recovery()) {
+            Some(recovery) => recovery,
+            None => return Vec::new(),
+        };
+
+        let mut file_lines = self.content.lock().await;
+        file_lines.materialize();
+
+        let old_lines = file_lines.lines()
+            .map(|line| line.into_owned())
+            .collect::<Vec<_>>();
+        let changed = changed_lines(
+            &old_lines.iter().map(String::as_str).collect::<Vec<_>>(),
+            &recovery.split_inclusive('\n').collect::<Vec<_>>()
+        );
+
+        let end = file_lines.line_to_char(file_lines.len());
+        let change = Self::replace_range(&mut file_lines, 0..end, recovery);
+        self.history.record(change);
+        drop(file_lines);
+
+        self.file_modify().await;
+
+        changed
+    }
+
+    pub fn discard_swap(&mut self) {
+        if let Some(ref mut swap) = self.swap {
+            swap.take_recovery();
+        }
+    }
+
+    pub fn get_absolute(mut path: String) -> PathBuf {
         use path_absolutize::*;
 
         if path.starts_with("~/") {
@@ -303,19 +1298,37 @@ impl FileState {
 impl Default for FileState {
     fn default() -> Self {
         FileState {
-            path: PathBuf::default(),
-            content: Arc::new(Mutex::new(Vec::new())),
+            source: BufferSource::default(),
+            syntax: None,
+            content: Arc::new(Mutex::new(TextBuffer::default())),
             stylized: Arc::new(Mutex::new(Vec::new())),
             theme: ThemeSet::load_defaults().themes["base16-ocean.dark"].to_owned(),
             syntax_set: SyntaxSet::load_defaults_newlines(),
             background_color: None,
-            file_modified: Arc::new(Mutex::new(false))
+            file_modified: Arc::new(Mutex::new(false)),
+            history: History::default(),
+            encoding: FileEncoding::default(),
+            line_ending: LineEnding::default(),
+            final_newline: true,
+            swap: None,
+            opened_encoding: None,
+            disk_stamp: None,
+            disk_noticed: false,
+            new_file: false,
+            readonly: false,
+            swap_checked: false,
+            hex: None,
+            to_stdout: false,
+            output: None,
+            previous_binding: None
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::utils::TempDir;
+
     use super::*;
 
     #[test]
@@ -323,11 +1336,58 @@ mod tests {
         let runtime = tokio::runtime::Runtime::new().unwrap();
         runtime.block_on(async {
             let mut file_state = FileState::default();
-            file_state.init(String::from("/home/spring/test.el")).await?;
+            file_state.init(String::from("/home/spring/test.el"), None).await?;
 
             println!("{:#?}", file_state.content);
 
             Ok::<(), AppError>(())
         }).unwrap();
     }
+
+    #[tokio::test]
+    async fn toggle_hex_test() {
+        let mut file_state = FileState::default();
+        *file_state.content.lock().await = TextBuffer::from_text("ab\nc\nd\n");
+        file_state.encoding = FileEncoding::from_label("utf-8 bom").unwrap();
+        file_state.line_ending = LineEnding::CrLf;
+
+        // The BOM & the line breaks before cursor are counted.
+        assert_eq!(file_state.toggle_hex((1, 2)).await.unwrap(), (10, 0));
+
+        for (idx, byte) in [(5, b'x'), (8, b'y'), (12, b'z')] {
+            file_state.set_hex_byte(idx, byte).await;
+        }
+
+        file_state.toggle_hex((0, 0)).await.unwrap();
+        assert_eq!(file_state.line_ending, LineEnding::Lf);
+        assert!(!file_state.final_newline);
+        assert_eq!(file_state.content.lock().await.line(0), "abx\n");
+
+        file_state.history.commit((0, 0));
+        file_state.undo().await.unwrap();
+        assert_eq!((file_state.line_ending, file_state.final_newline), (LineEnding::CrLf, true));
+        assert_eq!(file_state.content.lock().await.line(0), "ab\n");
+
+        file_state.redo().await.unwrap();
+        assert_eq!((file_state.line_ending, file_state.final_newline), (LineEnding::Lf, false));
+    }
+
+    #[tokio::test]
+    async fn mapped_text_test() {
+        let dir = TempDir::new("mapped_text_test");
+        let path = dir.join("file.txt");
+        std::fs::write(&path, "one\r\ntwo 漢字\nthree\n").unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mmap = unsafe { Mmap::map(&file).unwrap() };
+
+        let file_state = FileState::default();
+        *file_state.content.lock().await = TextBuffer::from_mmap(mmap, FileEncoding::default());
+
+        assert_eq!(file_state.get_text((1, 0), (5, 1)).await.unwrap(), "ne\ntwo 漢");
+        assert_eq!(file_state.get_text((0, 1), (0, 2)).await.unwrap(), "two 漢字\n");
+        assert_eq!(file_state.get_text((2, 2), (6, 2)).await.unwrap(), "ree\n");
+        assert!(file_state.get_text((1, 1), (0, 1)).await.is_err());
+        assert!(file_state.content.lock().await.is_mapped());
+    }
 }
@@ -0,0 +1,109 @@
+// Hex Buffer
+
+use super::encoding::FileEncoding;
+
+/// The size of content used to detect binary file.
+const DETECT_SIZE: usize = 8000;
+
+/// The raw bytes of file edited in hex view.
+#[derive(Debug, Clone, Default)]
+pub struct HexBuffer {
+    bytes: Vec<u8>,
+
+    // Whether the bytes have been changed since the hex view was opened.
+    edited: bool
+}
+
+impl HexBuffer {
+    /// The count of bytes shown in each row of hex view.
+    pub const ROW_SIZE: usize = 16;
+
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, edited: false }
+    }
+
+    /// Whether the bytes are binary data rather than text, which is judged by the NUL
+    /// bytes at the beginning of content. The ones of UTF-16 text are excluded.
+    pub fn is_binary(bytes: &[u8]) -> bool {
+        let sample = &bytes[..bytes.len().min(DETECT_SIZE)];
+
+        memchr::memchr(0, sample).is_some() && FileEncoding::detect(sample).is_ascii_compatible()
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn edited(&self) -> bool {
+        self.edited
+    }
+
+    /// Get the count of rows, there's an empty row for empty content.
+    pub fn rows(&self) -> usize {
+        self.bytes.len().div_ceil(Self::ROW_SIZE).max(1)
+    }
+
+    pub fn row(&self, idx: usize) -> &[u8] {
+        let start = (idx * Self::ROW_SIZE).min(self.bytes.len());
+        let end = (start + Self::ROW_SIZE).min(self.bytes.len());
+
+        &self.bytes[start..end]
+    }
+
+    /// Overwrite the byte at `idx`, return false when it's out of content.
+    pub fn set_byte(&mut self, idx: usize, byte: u8) -> bool {
+        match self.bytes.get_mut(idx) {
+            Some(_byte) => {
+                *_byte = byte;
+                self.edited = true;
+
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Overwrite the high or low 4 bits of the byte at `idx` with `value`.
+    pub fn set_nibble(&mut self, idx: usize, low: bool, value: u8) -> bool {
+        let byte = match self.bytes.get(idx) {
+            Some(byte) => *byte,
+            None => return false,
+        };
+
+        self.set_byte(idx, if low {
+            (byte & 0xF0) | (value & 0x0F)
+        } else {
+            (byte & 0x0F) | (value << 4)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_buffer_test() {
+        assert!(HexBuffer::is_binary(b"\x7FELF\x02\x01\x01\x00\x00\x00"));
+        assert!(!HexBuffer::is_binary(b"plain text\n"));
+
+        let utf16 = "plain text\n".encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect::<Vec<_>>();
+        assert!(!HexBuffer::is_binary(&utf16));
+
+        let mut hex = HexBuffer::new((0..20).collect());
+        assert_eq!(hex.rows(), 2);
+        assert_eq!(hex.row(1), &[16, 17, 18, 19]);
+
+        assert!(hex.set_nibble(1, false, 0xA));
+        assert!(hex.set_nibble(1, true, 0xB));
+        assert!(!hex.set_byte(20, 0));
+        assert_eq!(hex.bytes()[1], 0xAB);
+        assert!(hex.edited());
+    }
+}
@@ -0,0 +1,195 @@
+// Edit History
+
+use super::line_ending::LineEnding;
+
+/// A single reversible modification of the file content.
+/// The text `removed` starting from char index `at` was replaced by `inserted`.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub at: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+/// The line ending & whether the file ends with a line break, which are changed
+/// along with the content edited in hex view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileFormat {
+    pub line_ending: LineEnding,
+    pub final_newline: bool,
+}
+
+/// A group of changes which will be undone or redone at once.
+#[derive(Debug, Clone)]
+pub struct UndoStep {
+    pub changes: Vec<Change>,
+    pub cursor_before: (usize, usize),
+    pub cursor_after: (usize, usize),
+
+    // The file format before & after the step, when it's changed.
+    pub format: Option<(FileFormat, FileFormat)>,
+}
+
+/// The undo & redo history of a file.
+/// Changes are collected into the pending step until it's committed.
+#[derive(Debug, Default)]
+pub struct History {
+    undo_stack: Vec<UndoStep>,
+    redo_stack: Vec<UndoStep>,
+    pending: Option<UndoStep>,
+
+    // The depth of undo stack when the file was saved.
+    // None means the saved state cannot be reached any more.
+    saved_depth: Option<usize>,
+}
+
+impl Change {
+    /// Return the change which reverts current one.
+    pub fn invert(&self) -> Self {
+        Self {
+            at: self.at,
+            removed: self.inserted.to_owned(),
+            inserted: self.removed.to_owned(),
+        }
+    }
+}
+
+impl UndoStep {
+    fn new(cursor: (usize, usize)) -> Self {
+        Self {
+            changes: Vec::new(),
+            cursor_before: cursor,
+            cursor_after: cursor,
+            format: None,
+        }
+    }
+}
+
+impl History {
+    /// Start a new step with the cursor position before editing.
+    /// Nothing will happen if there's already a pending step.
+    pub fn begin(&mut self, cursor: (usize, usize)) {
+        if self.pending.is_none() {
+            self.pending = Some(UndoStep::new(cursor));
+        }
+    }
+
+    pub fn record(&mut self, change: Change) {
+        self.pending
+            .get_or_insert_with(|| UndoStep::new((0, 0)))
+            .changes
+            .push(change);
+    }
+
+    /// Record the change of file format, the format before the step is kept.
+    pub fn record_format(&mut self, before: FileFormat, after: FileFormat) {
+        let step = self.pending.get_or_insert_with(|| UndoStep::new((0, 0)));
+
+        step.format = match step.format {
+            Some((first, _)) => Some((first, after)),
+            None => Some((before, after)),
+        };
+    }
+
+    /// Push the pending step into undo stack.
+    /// Return false when there's nothing changed.
+    pub fn commit(&mut self, cursor: (usize, usize)) -> bool {
+        let mut step = match self.pending.take() {
+            Some(step) => step,
+            None => return false,
+        };
+
+        if step.changes.is_empty() && step.format.is_none_or(|(before, after)| before == after) {
+            return false
+        }
+
+        if let Some(depth) = self.saved_depth {
+            if depth > self.undo_stack.len() {
+                self.saved_depth = None;
+            }
+        }
+
+        step.cursor_after = cursor;
+        self.undo_stack.push(step);
+        self.redo_stack.clear();
+
+        true
+    }
+
+    /// Pop the latest step to be undone, which will be moved into redo stack.
+    pub fn undo(&mut self) -> Option<UndoStep> {
+        let step = self.undo_stack.pop()?;
+        self.redo_stack.push(step.to_owned());
+
+        Some(step)
+    }
+
+    /// Pop the latest undone step to be redone.
+    pub fn redo(&mut self) -> Option<UndoStep> {
+        let step = self.redo_stack.pop()?;
+        self.undo_stack.push(step.to_owned());
+
+        Some(step)
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_depth = Some(self.undo_stack.len());
+    }
+
+    /// Make the saved state unreachable, as the file will be different from now on.
+    pub fn unmark_saved(&mut self) {
+        self.saved_depth = None;
+    }
+
+    /// Whether the content is the same as the saved one.
+    pub fn at_saved(&self) -> bool {
+        self.saved_depth == Some(self.undo_stack.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(at: usize, removed: &str, inserted: &str) -> Change {
+        Change {
+            at,
+            removed: String::from(removed),
+            inserted: String::from(inserted),
+        }
+    }
+
+    #[test]
+    fn undo_redo_test() {
+        let mut history = History::default();
+        history.mark_saved();
+
+        history.begin((0, 0));
+        history.record(change(1, "", "b"));
+        history.record(change(2, "", "c"));
+        assert!(history.commit((3, 0)));
+        assert!(!history.at_saved());
+
+        // Empty step should not be recorded
+        history.begin((3, 0));
+        assert!(!history.commit((3, 0)));
+
+        let step = history.undo().unwrap();
+        assert_eq!(step.changes.len(), 2);
+        assert_eq!(step.cursor_before, (0, 0));
+        assert!(history.at_saved());
+        assert!(history.undo().is_none());
+
+        let step = history.redo().unwrap();
+        assert_eq!(step.cursor_after, (3, 0));
+        assert!(history.redo().is_none());
+
+        // New change after undoing clears redo stack and the saved state
+        history.undo();
+        history.begin((0, 0));
+        history.record(change(0, "a", "x"));
+        history.commit((0, 0));
+        assert!(history.redo().is_none());
+        assert!(!history.at_saved());
+    }
+}
@@ -0,0 +1,78 @@
+// Line Ending
+
+/// The size of content used to detect line ending.
+const DETECT_SIZE: usize = 64 * 1024;
+
+/// The line break style of a file.
+/// Lines are always stored with '\n' in buffer, and converted when saving.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf
+}
+
+impl LineEnding {
+    /// Detect with the line breaks at the beginning of file, the more used one wins.
+    pub fn detect(bytes: &[u8]) -> Self {
+        let sample = &bytes[..bytes.len().min(DETECT_SIZE)];
+        let (mut lf, mut crlf) = (0, 0);
+
+        for pos in memchr::memchr_iter(b'\n', sample) {
+            if pos > 0 && sample[pos - 1] == b'\r' {
+                crlf += 1;
+            } else {
+                lf += 1;
+            }
+        }
+
+        if crlf > lf {
+            Self::CrLf
+        } else {
+            Self::Lf
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+        }
+    }
+
+    pub fn switch(&self) -> Self {
+        match *self {
+            Self::Lf => Self::CrLf,
+            Self::CrLf => Self::Lf,
+        }
+    }
+
+    /// Convert the text from buffer into the text to be saved.
+    pub fn apply(&self, mut text: String, final_newline: bool) -> String {
+        if !final_newline && text.ends_with('\n') {
+            text.pop();
+        }
+
+        match *self {
+            Self::Lf => text,
+            Self::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_ending_test() {
+        assert_eq!(LineEnding::detect(b"a\r\nb\r\nc\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect(b"a\nb\r\nc\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect(b""), LineEnding::Lf);
+
+        let text = String::from("a\nb\n");
+        assert_eq!(LineEnding::CrLf.apply(text.to_owned(), true), "a\r\nb\r\n");
+        assert_eq!(LineEnding::CrLf.apply(text.to_owned(), false), "a\r\nb");
+        assert_eq!(LineEnding::Lf.apply(text, false), "a\nb");
+    }
+}
@@ -1,4 +1,20 @@
+mod history;
+mod hex_buffer;
+mod disk_stamp;
+mod encoding;
+mod line_ending;
+mod save;
+mod source;
+mod swap;
+mod position;
+mod text_buffer;
+mod transform;
 mod file_state;
 mod type_convert;
 
+pub use encoding::FileEncoding;
+pub use hex_buffer::HexBuffer;
+pub use swap::SwapState;
+pub use position::{Position, PositionStore};
+pub use text_buffer::TextBuffer;
 pub use file_state::{FileState, StylizedVec};
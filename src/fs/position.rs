@@ -0,0 +1,149 @@
+// Position Store
+
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use super::save::write_file;
+use crate::{error::AppResult, utils::state_dir};
+
+const POSITION_HEADER: &str = "tetor positions";
+
+/// The editing position of a file when it was left.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    pub cursor: (usize, usize),
+    pub offset: usize,
+    pub pattern: Option<String>
+}
+
+/// The positions of files edited before, which are kept across sessions.
+/// Each line of the state file is an entry, whose fields are separated by tabs.
+/// The recently left files are placed first.
+#[derive(Debug)]
+pub struct PositionStore {
+    path: PathBuf,
+    entries: Vec<(PathBuf, Position)>,
+
+    // The positions recorded by current instance.
+    recorded: Vec<(PathBuf, Position)>
+}
+
+impl Default for PositionStore {
+    fn default() -> Self {
+        Self::new(state_dir().join("positions"))
+    }
+}
+
+impl PositionStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, entries: Vec::new(), recorded: Vec::new() }
+    }
+
+    /// Read the positions from state file, the broken entries are ignored.
+    pub async fn load(&mut self) {
+        self.entries = match fs::read_to_string(&self.path).await {
+            Ok(text) => Self::parse(&text),
+            Err(_) => Vec::new(),
+        };
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Position> {
+        self.entries.iter()
+            .find(|(_path, _)| _path == path)
+            .map(|(_, position)| position)
+    }
+
+    pub fn record(&mut self, path: PathBuf, mut position: Position) {
+        // The fields containing separators cannot be kept in the entry.
+        if path.to_string_lossy().contains('\n') {
+            return
+        }
+        position.pattern = position.pattern.filter(|pattern| !pattern.contains('\t'));
+
+        self.recorded.push((path, position));
+    }
+
+    /// Write the recorded positions into state file, which keeps `limit` entries at most.
+    /// The file is read again, thus the positions recorded by others are kept.
+    pub async fn save(&mut self, limit: usize) -> AppResult<()> {
+        if self.recorded.is_empty() {
+            return Ok(())
+        }
+
+        self.load().await;
+
+        for (path, position) in self.recorded.drain(..) {
+            self.entries.retain(|(_path, _)| *_path != path);
+            self.entries.insert(0, (path, position));
+        }
+        self.entries.truncate(limit);
+
+        let mut text = format!("{}\n", POSITION_HEADER);
+        for (path, position) in self.entries.iter() {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                position.cursor.1,
+                position.cursor.0,
+                position.offset,
+                position.pattern.as_deref().unwrap_or(""),
+                path.display()
+            ));
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        write_file(&self.path, text.as_bytes(), false).await
+    }
+
+    fn parse(text: &str) -> Vec<(PathBuf, Position)> {
+        let mut lines = text.lines();
+
+        if lines.next() != Some(POSITION_HEADER) {
+            return Vec::new()
+        }
+
+        lines.filter_map(|line| {
+            let mut fields = line.splitn(5, '\t');
+            let line = fields.next()?.parse::<usize>().ok()?;
+            let col = fields.next()?.parse::<usize>().ok()?;
+            let offset = fields.next()?.parse::<usize>().ok()?;
+            let pattern = fields.next()?;
+            let path = fields.next()?;
+
+            Some((PathBuf::from(path), Position {
+                cursor: (col, line),
+                offset,
+                pattern: if pattern.is_empty() { None } else { Some(String::from(pattern)) }
+            }))
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn position_test() {
+        let dir = TempDir::new("position_test");
+        let position = Position { cursor: (3, 10), offset: 5, pattern: Some(String::from("a b")) };
+
+        let mut store = PositionStore::new(dir.join("positions"));
+        store.record(PathBuf::from("/tmp/a.txt"), Position::default());
+        store.record(PathBuf::from("/tmp/b c.txt"), position.clone());
+        store.record(PathBuf::from("/tmp/d.txt"), Position::default());
+        store.save(2).await.unwrap();
+
+        let mut store = PositionStore::new(dir.join("positions"));
+        store.load().await;
+        assert_eq!(store.get(Path::new("/tmp/b c.txt")), Some(&position));
+        assert_eq!(store.get(Path::new("/tmp/d.txt")), Some(&Position::default()));
+        assert_eq!(store.get(Path::new("/tmp/a.txt")), None);
+    }
+}
@@ -0,0 +1,167 @@
+// Safe Save
+
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+use tokio::io::{self, AsyncWriteExt, ErrorKind};
+
+use crate::error::{AppError, AppResult, ErrorType};
+
+/// Write bytes into the file at `path` atomically.
+/// The content is written into a temporary file in the same directory, which replaces the
+/// original file after being synced. When `backup` is true, the previous version is kept
+/// in the file with suffix '~'.
+pub async fn write_file(path: &Path, bytes: &[u8], backup: bool) -> AppResult<()> {
+    // Replace the file which the symlink points to, rather than the symlink itself.
+    let target = match fs::canonicalize(path).await {
+        Ok(target) => target,
+        Err(err) if err.kind() == ErrorKind::NotFound => path.to_path_buf(),
+        Err(err) => return Err(save_error(path, err)),
+    };
+
+    let metadata = match fs::metadata(&target).await {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => return Err(save_error(path, err)),
+    };
+
+    if backup && metadata.is_some() {
+        fs::copy(&target, backup_path(&target)).await
+            .map_err(|err| save_error(path, err))?;
+    }
+
+    // Renaming would break hard links.
+    if metadata.as_ref().is_some_and(|metadata| metadata.nlink() > 1) {
+        return overwrite(&target, bytes).await
+            .map_err(|err| save_error(path, err))
+    }
+
+    let temp = temp_path(&target);
+    match write_temp(&temp, bytes, metadata.as_ref()).await {
+        Ok(true) => (),
+        // The owner of original file cannot be kept, so it's overwritten directly.
+        Ok(false) => {
+            let _ = fs::remove_file(&temp).await;
+
+            return overwrite(&target, bytes).await
+                .map_err(|err| save_error(path, err))
+        },
+        Err(err) => {
+            let _ = fs::remove_file(&temp).await;
+            return Err(save_error(path, err))
+        },
+    }
+
+    if let Err(err) = fs::rename(&temp, &target).await {
+        let _ = fs::remove_file(&temp).await;
+        return Err(save_error(path, err))
+    }
+
+    // Make sure the renaming is written to disk, which may be unsupported by some file systems.
+    if let Some(parent) = target.parent() {
+        if let Ok(dir) = fs::File::open(parent).await {
+            let _ = dir.sync_all().await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the temporary file with the permissions and owner of original file.
+/// Return false when the owner cannot be changed.
+async fn write_temp(temp: &Path, bytes: &[u8], metadata: Option<&Metadata>) -> io::Result<bool> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    let mut file = match options.open(temp).await {
+        Ok(file) => file,
+        // Left by a crashed saving
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            fs::remove_file(temp).await?;
+            options.open(temp).await?
+        },
+        Err(err) => return Err(err),
+    };
+
+    file.write_all(bytes).await?;
+    file.flush().await?;
+    file.sync_all().await?;
+
+    if let Some(metadata) = metadata {
+        fs::set_permissions(temp, metadata.permissions()).await?;
+
+        let owner = (metadata.uid(), metadata.gid());
+        let temp_metadata = file.metadata().await?;
+
+        if owner != (temp_metadata.uid(), temp_metadata.gid())
+            && std::os::unix::fs::chown(temp, Some(owner.0), Some(owner.1)).is_err()
+        {
+            return Ok(false)
+        }
+    }
+
+    Ok(true)
+}
+
+async fn overwrite(target: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(target).await?;
+
+    file.write_all(bytes).await?;
+    file.flush().await?;
+    file.sync_all().await
+}
+
+fn temp_path(target: &Path) -> PathBuf {
+    let name = target.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    target.with_file_name(format!(".{}.{}.tetor-tmp", name, std::process::id()))
+}
+
+fn backup_path(target: &Path) -> PathBuf {
+    let mut path = target.as_os_str().to_owned();
+    path.push("~");
+
+    PathBuf::from(path)
+}
+
+fn save_error(path: &Path, err: io::Error) -> AppError {
+    ErrorType::Specific(
+        format!("Failed to save {}: {}", path.display(), err)
+    ).pack()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn write_file_test() {
+        let dir = TempDir::new("save_test");
+
+        let file = dir.join("file.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&file, "old").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        write_file(&link, b"new", true).await.unwrap();
+
+        assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(std::fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o640);
+        assert_eq!(std::fs::read_to_string(dir.join("file.txt~")).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 3);
+    }
+}
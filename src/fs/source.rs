@@ -0,0 +1,36 @@
+// Buffer Source
+
+use std::path::{Path, PathBuf};
+
+/// Where the content of buffer is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BufferSource {
+    File(PathBuf),
+    Stdin
+}
+
+impl Default for BufferSource {
+    fn default() -> Self {
+        Self::File(PathBuf::default())
+    }
+}
+
+impl BufferSource {
+    /// The path of file, which is None when the content isn't from a file.
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            Self::File(ref path) => Some(path),
+            Self::Stdin => None,
+        }
+    }
+
+    /// The name shown for buffer.
+    pub fn name(&self) -> String {
+        match *self {
+            Self::File(ref path) => path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.to_string_lossy().into_owned()),
+            Self::Stdin => String::from("[stdin]"),
+        }
+    }
+}
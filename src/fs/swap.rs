@@ -0,0 +1,179 @@
+// Swap File
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use tokio::fs;
+use tokio::io::ErrorKind;
+
+use super::save::write_file;
+use crate::error::AppResult;
+
+/// The minimum interval between two writings of swap file.
+const SWAP_INTERVAL: Duration = Duration::from_secs(4);
+
+const SWAP_HEADER: &str = "tetor swap";
+
+/// The state of swap file found when opening a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SwapState {
+    None,
+    InUse(u32),         // The pid of another instance editing the file
+    Recoverable
+}
+
+/// The file storing unsaved content, which is used for recovering after crash.
+/// It also records the pid of current instance, thus other instances can know the file
+/// is being edited.
+#[derive(Debug)]
+pub struct SwapFile {
+    path: PathBuf,
+
+    // The revision of buffer & modified state of the written content.
+    written: Option<(usize, bool)>,
+    last_write: Option<Instant>,
+
+    recovery: Option<String>,
+}
+
+impl SwapFile {
+    /// Create swap file for `file_path` in `dir`, named with its escaped path.
+    pub fn new(dir: &Path, file_path: &Path) -> Self {
+        let name = file_path.to_string_lossy().replace('/', "%");
+
+        Self {
+            path: dir.join(format!("{}.swp", name)),
+            written: None,
+            last_write: None,
+            recovery: None
+        }
+    }
+
+    /// Check the swap file left by other instances.
+    pub async fn check(&mut self) -> AppResult<SwapState> {
+        let text = match fs::read_to_string(&self.path).await {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(SwapState::None),
+            Err(err) => return Err(err.into()),
+        };
+
+        // The broken swap file will be overwritten.
+        let (pid, content) = match Self::parse(&text) {
+            Some(parsed) => parsed,
+            None => return Ok(SwapState::None),
+        };
+
+        if pid != std::process::id() && Self::process_alive(pid).await {
+            return Ok(SwapState::InUse(pid))
+        }
+
+        if let Some(content) = content {
+            self.recovery = Some(String::from(content));
+            return Ok(SwapState::Recoverable)
+        }
+
+        Ok(SwapState::None)
+    }
+
+    /// Take the content to be recovered.
+    pub fn take_recovery(&mut self) -> Option<String> {
+        self.recovery.take()
+    }
+
+    /// Whether the content should be written, which is limited by SWAP_INTERVAL.
+    pub fn need_write(&self, revision: usize, modified: bool) -> bool {
+        if self.written == Some((revision, modified)) {
+            return false
+        }
+
+        self.last_write.is_none_or(|time| time.elapsed() >= SWAP_INTERVAL)
+    }
+
+    /// Write the unsaved content into swap file.
+    /// The content is None when it's the same as the file.
+    pub async fn write(&mut self, revision: usize, content: Option<String>) -> AppResult<()> {
+        let modified = content.is_some();
+        let mut text = format!(
+            "{}\npid {}\nmodified {}\n",
+            SWAP_HEADER,
+            std::process::id(),
+            modified as u8
+        );
+
+        if let Some(content) = content {
+            text.push_str(&content);
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+
+        write_file(&self.path, text.as_bytes(), false).await?;
+
+        self.written = Some((revision, modified));
+        self.last_write = Some(Instant::now());
+
+        Ok(())
+    }
+
+    pub async fn remove(&self) {
+        let _ = fs::remove_file(&self.path).await;
+    }
+
+    /// Parse the pid and unsaved content from swap file.
+    fn parse(text: &str) -> Option<(u32, Option<&str>)> {
+        let mut parts = text.splitn(4, '\n');
+
+        if parts.next()? != SWAP_HEADER {
+            return None
+        }
+
+        let pid = parts.next()?.strip_prefix("pid ")?.parse::<u32>().ok()?;
+        let modified = parts.next()? == "modified 1";
+        let content = parts.next().unwrap_or("");
+
+        Some((pid, if modified { Some(content) } else { None }))
+    }
+
+    /// Whether the process with `pid` is still a running tetor.
+    async fn process_alive(pid: u32) -> bool {
+        let comm = fs::read_to_string(format!("/proc/{}/comm", pid)).await;
+        let self_comm = fs::read_to_string("/proc/self/comm").await;
+
+        match (comm, self_comm) {
+            (Ok(comm), Ok(self_comm)) => comm == self_comm,
+            _ => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn swap_test() {
+        let dir = TempDir::new("swap_test");
+
+        let mut swap = SwapFile::new(dir.path(), Path::new("/tmp/file.txt"));
+        assert_eq!(swap.check().await.unwrap(), SwapState::None);
+
+        assert!(swap.need_write(0, false));
+        swap.write(0, None).await.unwrap();
+        assert!(!swap.need_write(0, false));
+        assert!(dir.join("%tmp%file.txt.swp").exists());
+
+        // The swap file left by a dead instance
+        std::fs::write(
+            dir.join("%tmp%file.txt.swp"),
+            format!("{}\npid {}\nmodified 1\nunsaved\n", SWAP_HEADER, u32::MAX)
+        ).unwrap();
+        assert_eq!(swap.check().await.unwrap(), SwapState::Recoverable);
+        assert_eq!(swap.take_recovery().unwrap(), "unsaved\n");
+
+        swap.remove().await;
+        assert!(!dir.join("%tmp%file.txt.swp").exists());
+    }
+}
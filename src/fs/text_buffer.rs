@@ -0,0 +1,318 @@
+// Text Buffer
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use memmap2::Mmap;
+use ropey::{Rope, RopeBuilder};
+
+use super::encoding::FileEncoding;
+
+/// Every CHECKPOINT_STEP lines, the byte offset of line beginning is recorded for mapped file.
+const CHECKPOINT_STEP: usize = 128;
+
+/// The content of a file.
+/// Every line keeps its trailing '\n', thus the lines count equals to the count of line breaks.
+///
+/// Large files are mapped into memory and only indexed, they'll be loaded into rope
+/// when the content is going to be edited.
+#[derive(Debug)]
+pub struct TextBuffer {
+    storage: Storage,
+
+    // Increased by every edit, used to know whether the content has changed.
+    revision: usize
+}
+
+#[derive(Debug)]
+enum Storage {
+    Rope(Rope),
+    Mapped(MappedText)
+}
+
+/// The memory mapped file with a sparse index of line beginnings.
+/// Its encoding must be ASCII compatible, thus the line breaks can be found without decoding.
+#[derive(Debug)]
+struct MappedText {
+    mmap: Mmap,
+    encoding: FileEncoding,
+    checkpoints: Vec<usize>,
+    lines: usize
+}
+
+impl Default for TextBuffer {
+    fn default() -> Self {
+        Self { storage: Storage::Rope(Rope::new()), revision: 0 }
+    }
+}
+
+impl TextBuffer {
+    /// Create buffer from text, making sure each line ends with '\n'.
+    pub fn from_text(text: &str) -> Self {
+        let mut builder = RopeBuilder::new();
+
+        for line in text.lines() {
+            builder.append(line);
+            builder.append("\n");
+        }
+
+        Self { storage: Storage::Rope(builder.finish()), revision: 0 }
+    }
+
+    /// Create buffer from the mapped file without loading its content.
+    pub fn from_mmap(mmap: Mmap, encoding: FileEncoding) -> Self {
+        Self { storage: Storage::Mapped(MappedText::new(mmap, encoding)), revision: 0 }
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.storage, Storage::Mapped(_))
+    }
+
+    /// Load the whole mapped file into rope.
+    pub fn materialize(&mut self) {
+        if let Storage::Mapped(ref mapped) = self.storage {
+            let mut builder = RopeBuilder::new();
+
+            for line in mapped.lines_at(0) {
+                builder.append(&line);
+            }
+
+            self.storage = Storage::Rope(builder.finish());
+        }
+    }
+
+    /// Get the count of lines.
+    pub fn len(&self) -> usize {
+        let rope = match self.storage {
+            Storage::Rope(ref rope) => rope,
+            Storage::Mapped(ref mapped) => return mapped.lines,
+        };
+        let lines = rope.len_lines();
+
+        if rope.len_chars() == 0 || rope.char(rope.len_chars() - 1) == '\n' {
+            lines - 1
+        } else {
+            lines
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Get the line at `idx` with its line break.
+    pub fn line(&self, idx: usize) -> Cow<'_, str> {
+        match self.storage {
+            Storage::Rope(ref rope) => rope.line(idx).into(),
+            Storage::Mapped(ref mapped) => mapped.line_at(mapped.line_start(idx)).0,
+        }
+    }
+
+    /// Iterate lines starting from line `from`.
+    pub fn lines_at(&self, from: usize) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        let count = self.len().saturating_sub(from);
+
+        match self.storage {
+            Storage::Rope(ref rope) => Box::new(
+                rope.lines_at(from)
+                    .take(count)
+                    .map(Cow::from)
+            ),
+            Storage::Mapped(ref mapped) => Box::new(mapped.lines_at(from)),
+        }
+    }
+
+    pub fn lines(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        self.lines_at(0)
+    }
+
+    /// Iterate the text chunks of the whole buffer, which is cheap for writing.
+    pub fn chunks(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match self.storage {
+            Storage::Rope(ref rope) => Box::new(rope.chunks().map(Cow::from)),
+            Storage::Mapped(_) => self.lines(),
+        }
+    }
+
+    // NOTE: The methods below are only available for materialized content.
+
+    /// Get the char index of the beginning of line `idx`.
+    /// The index equal to lines count points to the end of buffer.
+    pub fn line_to_char(&self, idx: usize) -> usize {
+        let rope = self.rope();
+
+        if idx >= self.len() {
+            return rope.len_chars()
+        }
+
+        rope.line_to_char(idx)
+    }
+
+    /// Get the index of line which contains the char at `char_idx`.
+    pub fn char_to_line(&self, char_idx: usize) -> usize {
+        self.rope().char_to_line(char_idx)
+    }
+
+    pub fn slice(&self, range: Range<usize>) -> String {
+        self.rope().slice(range).to_string()
+    }
+
+    pub fn insert(&mut self, char_idx: usize, text: &str) {
+        self.materialize();
+        self.rope_mut().insert(char_idx, text);
+        self.revision += 1;
+    }
+
+    /// Remove the text within range, return the removed text.
+    pub fn remove(&mut self, range: Range<usize>) -> String {
+        self.materialize();
+
+        let removed = self.slice(range.to_owned());
+        self.rope_mut().remove(range);
+        self.revision += 1;
+
+        removed
+    }
+
+    fn rope(&self) -> &Rope {
+        match self.storage {
+            Storage::Rope(ref rope) => rope,
+            Storage::Mapped(_) => panic!("Error code 1 at rope in text_buffer.rs!"),
+        }
+    }
+
+    fn rope_mut(&mut self) -> &mut Rope {
+        match self.storage {
+            Storage::Rope(ref mut rope) => rope,
+            Storage::Mapped(_) => panic!("Error code 1 at rope_mut in text_buffer.rs!"),
+        }
+    }
+}
+
+impl MappedText {
+    fn new(mmap: Mmap, encoding: FileEncoding) -> Self {
+        let mut checkpoints = vec![encoding.bom_len(&mmap)];
+        let mut lines = 0;
+
+        for pos in memchr::memchr_iter(b'\n', &mmap) {
+            lines += 1;
+
+            if lines % CHECKPOINT_STEP == 0 {
+                checkpoints.push(pos + 1);
+            }
+        }
+
+        // The last line without line break
+        if mmap.last().is_some_and(|byte| *byte != b'\n') {
+            lines += 1;
+        }
+
+        Self { mmap, encoding, checkpoints, lines }
+    }
+
+    /// Get the byte offset of the beginning of line `idx`.
+    fn line_start(&self, idx: usize) -> usize {
+        let mut start = self.checkpoints[idx / CHECKPOINT_STEP];
+
+        for _ in 0..(idx % CHECKPOINT_STEP) {
+            start = self.line_end(start);
+        }
+
+        start
+    }
+
+    /// Get the byte offset after the line break of the line starting from `start`.
+    fn line_end(&self, start: usize) -> usize {
+        match memchr::memchr(b'\n', &self.mmap[start..]) {
+            Some(pos) => start + pos + 1,
+            None => self.mmap.len(),
+        }
+    }
+
+    /// Get the line starting from `start`, and the beginning of next line.
+    fn line_at(&self, start: usize) -> (Cow<'_, str>, usize) {
+        let end = self.line_end(start);
+        let mut bytes = &self.mmap[start..end];
+
+        if let Some(stripped) = bytes.strip_suffix(b"\n") {
+            bytes = stripped;
+        }
+
+        if let Some(stripped) = bytes.strip_suffix(b"\r") {
+            bytes = stripped;
+        }
+
+        let mut line = self.encoding.decode(bytes);
+        line.push('\n');
+
+        (Cow::Owned(line), end)
+    }
+
+    fn lines_at(&self, from: usize) -> impl Iterator<Item = Cow<'_, str>> {
+        let mut start = self.line_start(from);
+
+        std::iter::from_fn(move || {
+            if start >= self.mmap.len() {
+                return None
+            }
+
+            let (line, next) = self.line_at(start);
+            start = next;
+
+            Some(line)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn edit_test() {
+        let mut buffer = TextBuffer::from_text("fn main() {\r\n}");
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.line(0), "fn main() {\n");
+
+        let at = buffer.line_to_char(1);
+        buffer.insert(at, "    println!();\n");
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.lines_at(1).collect::<Vec<_>>(), vec!["    println!();\n", "}\n"]);
+
+        let removed = buffer.remove(at..buffer.line_to_char(2));
+        assert_eq!(removed, "    println!();\n");
+        assert_eq!(buffer.chunks().collect::<String>(), "fn main() {\n}\n");
+        assert_eq!(buffer.line_to_char(buffer.len()), 14);
+
+        assert!(TextBuffer::from_text("").is_empty());
+        assert_eq!(TextBuffer::from_text("").len(), 0);
+    }
+
+    #[test]
+    fn mapped_test() {
+        let dir = TempDir::new("mapped_test");
+        let path = dir.join("file.txt");
+        let text = (0..1000).map(|i| format!("line {}\r\n", i)).collect::<String>() + "last";
+        std::fs::write(&path, &text).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let mmap = unsafe { Mmap::map(&file).unwrap() };
+        let mut buffer = TextBuffer::from_mmap(mmap, FileEncoding::default());
+        assert!(buffer.is_mapped());
+        assert_eq!(buffer.len(), 1001);
+        assert_eq!(buffer.line(300), "line 300\n");
+        assert_eq!(buffer.line(1000), "last\n");
+        assert_eq!(buffer.lines_at(999).collect::<Vec<_>>(), vec!["line 999\n", "last\n"]);
+
+        buffer.insert(0, "first\n");
+        assert!(!buffer.is_mapped());
+        assert_eq!(buffer.len(), 1002);
+        assert_eq!(buffer.line(301), "line 300\n");
+    }
+}
@@ -0,0 +1,82 @@
+// Save Transform
+
+use crate::{config::SaveTransform, utils::display_width};
+
+/// Apply the transformations of lines to `text`, whose lines all end with '\n'.
+pub fn transform_text(
+    text: &str,
+    transform: &SaveTransform,
+    tab_indent: bool,
+    tab_width: usize
+) -> String
+{
+    let mut lines = text.split_inclusive('\n')
+        .map(|line| {
+            let mut line = line.strip_suffix('\n').unwrap_or(line);
+
+            if transform.trim_trailing_whitespace {
+                line = line.trim_end_matches([' ', '\t']);
+            }
+
+            if transform.convert_indent {
+                format!("{}\n", convert_indent(line, tab_indent, tab_width))
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // The content keeps a line at least.
+    if transform.trim_trailing_lines {
+        while lines.len() > 1 && lines.last().is_some_and(|line| line.trim().is_empty()) {
+            lines.pop();
+        }
+    }
+
+    lines.concat()
+}
+
+/// Rebuild the indentation of `line` with tabs or spaces, keeping its display width.
+fn convert_indent(line: &str, tab_indent: bool, tab_width: usize) -> String {
+    let end = line.find(|_char: char| !matches!(_char, ' ' | '\t'))
+        .unwrap_or(line.len());
+    let width = (0..end).fold(0, |x, idx| x + display_width(&line[idx..idx + 1], x, tab_width));
+
+    let indent = if tab_indent {
+        format!("{}{}", "\t".repeat(width / tab_width), " ".repeat(width % tab_width))
+    } else {
+        " ".repeat(width)
+    };
+
+    format!("{}{}", indent, &line[end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_test() {
+        let text = "fn main() {  \n\t  let a = 1;\t\n}\n\n \n";
+        let mut transform = SaveTransform::default();
+        assert_eq!(transform_text(text, &transform, false, 4), text);
+
+        transform.trim_trailing_whitespace = true;
+        assert_eq!(
+            transform_text(text, &transform, false, 4),
+            "fn main() {\n\t  let a = 1;\n}\n\n\n"
+        );
+
+        transform.trim_trailing_lines = true;
+        transform.convert_indent = true;
+        assert_eq!(
+            transform_text(text, &transform, false, 4),
+            "fn main() {\n      let a = 1;\n}\n"
+        );
+        assert_eq!(
+            transform_text("      a\n", &transform, true, 4),
+            "\t  a\n"
+        );
+        assert_eq!(transform_text("\n\n", &transform, true, 4), "\n");
+    }
+}
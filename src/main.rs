@@ -6,8 +6,8 @@ mod error;
 mod config;
 mod command;
 
-use std::time::Duration;
-use std::io::stderr;
+use std::time::{Duration, Instant};
+use std::io::{stderr, stdout, Write};
 
 use ratatui::{
     Terminal,
@@ -16,7 +16,7 @@ use ratatui::{
         execute,
         event::poll,
         cursor::{Hide, Show},
-        event::{self, KeyEventKind},
+        event::{self, DisableFocusChange, EnableFocusChange, KeyEventKind},
         terminal::{
             enable_raw_mode,
             disable_raw_mode,
@@ -28,24 +28,28 @@ use ratatui::{
 
 use tokio::runtime::Runtime;
 
-use app::{handle_input, App};
-use error::{AppResult, ErrorType};
+use app::{handle_input, App, AppArgs};
+use error::AppResult;
+
+/// The interval of checking whether the file is changed on disk.
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(2);
 
 fn main() -> AppResult<()> {
     // Frame init
     let backend = CrosstermBackend::new(stderr());
     let mut terminal = Terminal::new(backend)?;
 
-    let mut args: Vec<String> = std::env::args().collect();
-    handle_cli_args(&mut args)?;
+    let args = AppArgs::parse(std::env::args().skip(1))?;
 
     let mut app = App::new();
     let rt = Runtime::new().unwrap();
 
-    rt.block_on(app.init_app(args[1].to_owned()))?;
+    rt.block_on(app.init_app(args))?;
 
     enable_raw_mode()?;
-    execute!(stderr(), EnterAlternateScreen, Hide)?;
+    execute!(stderr(), EnterAlternateScreen, Hide, EnableFocusChange)?;
+
+    let mut disk_checked = Instant::now();
 
     loop {
         terminal.draw(|frame| {
@@ -65,47 +69,47 @@ fn main() -> AppResult<()> {
             break;
         }
 
+        let mut focus_gained = false;
+
         if poll(Duration::from_millis(200))? {
-            if let event::Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+            match event::read()? {
+                event::Event::Key(key) if key.kind == KeyEventKind::Press => {
                     match handle_input(&mut app, key.code, &rt) {
                         Ok(_) => (),
                         Err(err) => app.app_errors.append_errors(
                             err.into_iter()
                         ),
                     }
-                }
+                },
+                event::Event::FocusGained => focus_gained = true,
+                _ => ()
             }
         }
-    }
 
-    execute!(stderr(), LeaveAlternateScreen, Show)?;
-    disable_raw_mode()?;
-
-    Ok(())
-}
+        if let Err(err) = rt.block_on(app.file_state.write_swap()) {
+            app.app_errors.append_errors(err.into_iter());
+        }
 
-// TODO: Is this necessary?
-fn handle_cli_args(args: &mut Vec<String>) -> AppResult<()> {
-    let err = ErrorType::Specific(
-        String::from("Wrong arguments for this app!")
-    ).pack();
+        if focus_gained || disk_checked.elapsed() >= DISK_CHECK_INTERVAL {
+            disk_checked = Instant::now();
 
-    if args.len() < 2 {
-        return Err(err)
+            if let Err(err) = rt.block_on(app.check_disk()) {
+                app.app_errors.append_errors(err.into_iter());
+            }
+        }
     }
 
-    loop {
-        if args.len() == 0 {
-            return Err(err)
-        }
+    rt.block_on(app.save_positions());
+    rt.block_on(app.remove_swaps());
 
-        if args[0] == "tetor" {
-            break;
-        }
+    execute!(stderr(), LeaveAlternateScreen, Show, DisableFocusChange)?;
+    disable_raw_mode()?;
 
-        args.remove(0);
+    // The saved content is written after leaving the terminal UI.
+    for output in app.take_outputs() {
+        stdout().write_all(&output)?;
     }
+    stdout().flush()?;
 
     Ok(())
 }
@@ -1,11 +1,14 @@
 // Command Edit
 
+use std::path::PathBuf;
+
 use ratatui::crossterm::event::KeyCode;
 
 use crate::{
     app::App,
     command::CommandPrior,
-    error::{AppResult, ErrorType}
+    error::{AppResult, ErrorType},
+    fs::FileState
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -15,12 +18,23 @@ pub enum CommandEdit {
 }
 
 impl CommandEdit {
+    /// The cursor is the char index of content.
     pub fn new(init_str: String, cmd: CommandPrior) -> Self {
-        let cursor = init_str.len();
+        let cursor = init_str.chars().count();
 
         Self::Some(init_str, cursor, cmd)
     }
 
+    /// The prompt displayed before content, which cannot be edited.
+    pub fn prompt(cmd: &CommandPrior) -> &'static str {
+        match *cmd {
+            CommandPrior::SetEncoding(_) => "Encoding: ",
+            CommandPrior::OpenFile(_) => "Open: ",
+            CommandPrior::SaveAs(_) => "Save as: ",
+            _ => ""
+        }
+    }
+
     /// Return a boolean value. When it's true, means the user have pressed Enter.
     pub fn edit(app: &mut App, key: KeyCode) -> AppResult<bool> {
         let command_edit = &mut app.command_edit;
@@ -36,7 +50,7 @@ impl CommandEdit {
                 },
 
                 KeyCode::Right => {
-                    if *cursor != content.len() {
+                    if *cursor != content.chars().count() {
                         *cursor += 1;
                     }
                 },
@@ -46,26 +60,45 @@ impl CommandEdit {
                         return Ok(false)
                     }
 
-                    content.remove(*cursor - 1);
+                    content.remove(Self::byte_idx(content, *cursor - 1));
                     *cursor -= 1;
                 },
 
                 KeyCode::Char(_key) => {
-                    if *cursor == content.len() {
+                    if *cursor == content.chars().count() {
                         content.push(_key);
                         *cursor += 1;
                         return Ok(false)
                     }
 
-                    content.insert(*cursor, _key);
+                    content.insert(Self::byte_idx(content, *cursor), _key);
                     *cursor += 1;
                 },
 
+                // Complete the file name for the commands taking a path.
+                KeyCode::Tab => {
+                    if let CommandPrior::OpenFile(_) | CommandPrior::SaveAs(_) = *cmd {
+                        if let Some(completed) = Self::complete_path(content) {
+                            *cursor = completed.chars().count();
+                            *content = completed;
+                        }
+                    }
+                },
+
                 KeyCode::Enter => {
                     match *cmd {
                         CommandPrior::Search(_) => app.prior_command = CommandPrior::Search(
                             content.to_owned()
                         ),
+                        CommandPrior::SetEncoding(_) => app.prior_command = CommandPrior::SetEncoding(
+                            content.to_owned()
+                        ),
+                        CommandPrior::OpenFile(_) => app.prior_command = CommandPrior::OpenFile(
+                            content.to_owned()
+                        ),
+                        CommandPrior::SaveAs(_) => app.prior_command = CommandPrior::SaveAs(
+                            content.to_owned()
+                        ),
                         _ => {
                             *command_edit = Self::None;
 
@@ -87,4 +120,79 @@ impl CommandEdit {
 
         Ok(false)
     }
+
+    /// Complete `path` with the longest common prefix of the file names matching it.
+    /// The hidden files are only matched when the name to be completed starts with '.'.
+    fn complete_path(path: &str) -> Option<String> {
+        let (dir, prefix) = match path.rfind('/') {
+            Some(idx) => path.split_at(idx + 1),
+            None => ("", path),
+        };
+
+        let dir_path = if dir.is_empty() {
+            PathBuf::from(".")
+        } else {
+            FileState::get_absolute(String::from(dir))
+        };
+
+        let mut matched = std::fs::read_dir(dir_path).ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let mut name = entry.file_name().into_string().ok()?;
+                if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                    return None
+                }
+
+                if entry.path().is_dir() {
+                    name.push('/');
+                }
+
+                Some(name)
+            })
+            .collect::<Vec<_>>();
+        matched.sort();
+
+        let first = matched.first()?;
+        let common = matched.iter().fold(first.chars().count(), |len, name| {
+            first.chars()
+                .zip(name.chars())
+                .take(len)
+                .take_while(|(char_a, char_b)| char_a == char_b)
+                .count()
+        });
+        let common = first.chars().take(common).collect::<String>();
+
+        Some(format!("{}{}", dir, common))
+    }
+
+    fn byte_idx(content: &str, cursor: usize) -> usize {
+        content.char_indices()
+            .nth(cursor)
+            .map(|(idx, _)| idx)
+            .unwrap_or(content.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn complete_path_test() {
+        let dir = TempDir::new("complete_test");
+        std::fs::create_dir_all(dir.join("source")).unwrap();
+        std::fs::write(dir.join("main.rs"), "").unwrap();
+        std::fs::write(dir.join("main.toml"), "").unwrap();
+        std::fs::write(dir.join(".hidden"), "").unwrap();
+
+        let base = format!("{}/", dir.path().display());
+        let complete = |name: &str| CommandEdit::complete_path(&format!("{}{}", base, name));
+
+        assert_eq!(complete("ma").unwrap(), format!("{}main.", base));
+        assert_eq!(complete("s").unwrap(), format!("{}source/", base));
+        assert_eq!(complete(".h").unwrap(), format!("{}.hidden", base));
+        assert!(complete("none").is_none());
+    }
 }
@@ -9,26 +9,32 @@ use ratatui::{
 
 use tokio::sync::Mutex;
 
+use std::ops::Range;
 use std::sync::Arc;
 
-use crate::{app::SearchIndicates, utils::cursor_compare_swap, fs::StylizedVec};
-use super::modal::Modal;
+use crate::{
+    app::SearchIndicates,
+    fs::StylizedVec,
+    utils::{cursor_compare_swap, display_width}
+};
+use super::{modal::Modal, HexCursor};
 
 /// The editor state for Editor widget.
 /// What needs to be stress on is that cursor_pos is the absolute position for the file.
 /// Not the buffer.
 #[derive(Debug, Clone, Copy)]
 pub struct EditorState {
-    cursor_pos: (u16, u16),
-    mark_point: Option<(u16, u16)>,
+    cursor_pos: (usize, usize),
+    mark_point: Option<(usize, usize)>,
 
     vertical_offset: usize,
-    horizontal_offset: u16,
+    horizontal_offset: usize,
 
     editor_height: Option<isize>,
     file_linenr: usize,
 
     pub modal: Modal,
+    pub hex_cursor: HexCursor,
     pub scrolling: bool
 }
 
@@ -37,6 +43,7 @@ pub struct Editor {
     lines: Arc<Mutex<StylizedVec>>,
     search_indicates: Arc<Mutex<SearchIndicates>>,
     background_color: Color,
+    tab_width: usize,
     render_cursor: bool
 }
 
@@ -53,6 +60,7 @@ impl Default for EditorState {
             file_linenr: 0,
             editor_height: None,
             modal: Modal::default(),
+            hex_cursor: HexCursor::default(),
         }
     }
 }
@@ -66,11 +74,11 @@ impl EditorState {
         &mut self.vertical_offset
     }
 
-    pub fn cursor(&self) -> (u16, u16) {
+    pub fn cursor(&self) -> (usize, usize) {
         self.cursor_pos
     }
 
-    pub fn cursor_mut(&mut self) -> &mut (u16, u16) {
+    pub fn cursor_mut(&mut self) -> &mut (usize, usize) {
         &mut self.cursor_pos
     }
 
@@ -78,11 +86,11 @@ impl EditorState {
         self.editor_height.expect("Error code 1 at height in editor.rs!")
     }
 
-    pub fn mark(&self) -> Option<(u16, u16)> {
+    pub fn mark(&self) -> Option<(usize, usize)> {
         self.mark_point
     }
 
-    pub fn mark_mut(&mut self) -> &mut Option<(u16, u16)> {
+    pub fn mark_mut(&mut self) -> &mut Option<(usize, usize)> {
         &mut self.mark_point
     }
 
@@ -93,36 +101,46 @@ impl EditorState {
     pub fn update(&mut self, area: Rect) -> bool {
         let mut to_update = false;
 
-        // Adjust window size
+        // Adjust window size, the content of a buffer rendered at the first time
+        // also needs to be updated.
         if self.editor_height.is_none() ||
             self.editor_height.unwrap() != area.height as isize
         {
-            if !self.editor_height.is_none() {
-                to_update = true;
-            }
+            to_update = true;
             self.editor_height = Some(area.height as isize);
         }
 
         // Adjust vertical_offset & cursor position.
-        if self.cursor_pos.1 < self.vertical_offset as u16 {
+        let height = area.height as usize;
+        if self.cursor_pos.1 < self.vertical_offset {
             if self.scrolling {
-                self.cursor_pos.1 = self.vertical_offset as u16;
+                self.cursor_pos.1 = self.vertical_offset;
             } else {
-                self.vertical_offset = self.cursor_pos.1 as usize;
+                self.vertical_offset = self.cursor_pos.1;
             }
 
             to_update = true;
-        } else if self.cursor_pos.1 >= area.height + self.vertical_offset as u16 {
+        } else if self.cursor_pos.1 >= height + self.vertical_offset {
             if self.scrolling {
-                self.cursor_pos.1 = self.vertical_offset as u16 + area.height - 1;
+                self.cursor_pos.1 = self.vertical_offset + height - 1;
             } else {
-                self.vertical_offset = (self.cursor_pos.1 - area.height / 2) as usize;
+                self.vertical_offset = self.cursor_pos.1 - height / 2;
             }
 
             to_update = true;
         }
 
-        // Adjust horizontal_offset
+        // To avoid this variable make impact on other motion
+        // after page_scroll reached to edges.
+        if self.scrolling {
+            self.scrolling = false;
+        }
+
+        to_update
+    }
+
+    /// Adjust horizontal_offset with the display columns taken by the grapheme at cursor.
+    pub fn update_horizontal(&mut self, area: Rect, cursor_range: Range<usize>) {
         let noncontent_width = {
             let length = Editor::nr_length(self.file_linenr);
             if length <= 4 {
@@ -135,19 +153,12 @@ impl EditorState {
             panic!("The editor is not suitable for large files.")
         }
 
-        if self.horizontal_offset > self.cursor_pos.0 {
-            self.horizontal_offset = self.cursor_pos.0;
-        } else if self.cursor_pos.0 - self.horizontal_offset + 1 >= area.width - noncontent_width {
-            self.horizontal_offset = self.cursor_pos.0 - area.width / 2;
-        }
-
-        // To avoid this variable make impact on other motion
-        // after page_scroll reached to edges.
-        if self.scrolling {
-            self.scrolling = false;
+        let content_width = (area.width - noncontent_width) as usize;
+        if self.horizontal_offset > cursor_range.start {
+            self.horizontal_offset = cursor_range.start;
+        } else if cursor_range.end - self.horizontal_offset >= content_width {
+            self.horizontal_offset = cursor_range.end.saturating_sub(area.width as usize / 2);
         }
-
-        to_update
     }
 }
 
@@ -156,12 +167,14 @@ impl Editor {
         content: Arc<Mutex<StylizedVec>>,
         indicates: Arc<Mutex<SearchIndicates>>,
         bg: Color,
+        tab_width: usize,
         render_cursor: bool
     ) -> Self {
         Editor {
             lines: content,
             search_indicates: indicates,
             background_color: bg,
+            tab_width,
             render_cursor
         }
     }
@@ -172,7 +185,7 @@ impl Editor {
     }
 
     /// Check whether cursor is within the marked region.
-    fn within_mark(state: &EditorState, x: u16, y: u16) -> bool {
+    fn within_mark(state: &EditorState, x: usize, y: usize) -> bool {
         if state.mark().is_none() {
             return false
         }
@@ -201,10 +214,10 @@ impl Editor {
         false
     }
 
-    fn is_cursor(&self, x: u16, y: usize, state: &EditorState) -> bool {
+    fn is_cursor(&self, x: usize, y: usize, state: &EditorState) -> bool {
         self.render_cursor &&
             state.cursor_pos.0 == x &&
-            state.cursor_pos.1 == y as u16
+            state.cursor_pos.1 == y
     }
 
     fn color_reverse(&self, buf: &mut Buffer, style: Style, x: u16, y: u16) {
@@ -274,7 +287,7 @@ impl StatefulWidget for Editor {
 
             // Render line number
             for x in 0..buf_x {
-                if state.cursor_pos.1 == file_line as u16 {
+                if state.cursor_pos.1 == file_line {
                     buf.get_mut(x, buf_y)
                         .set_fg(Color::Black)
                         .set_bg(Color::White);
@@ -294,75 +307,56 @@ impl StatefulWidget for Editor {
             buf_x += 1;
 
             // Render content
-            'line: for (style, span) in line.get_iter() {
-                for _char in span.chars() {
-                    // Stop rendering current line
-                    if buf_x == area.width {
-                        break 'line;
-                    }
+            let content_x = buf_x as usize;
+            let mut display_x = 0; // The display column of current grapheme in the line
+            for (style, grapheme) in line.graphemes().iter() {
+                let width = display_width(grapheme, display_x, self.tab_width);
+
+                // Skip the content before horizontal offset,
+                // including the wide grapheme crossing the left edge.
+                if display_x < state.horizontal_offset {
+                    current_length += 1;
+                    display_x += width;
+                    continue;
+                }
 
-                    if current_length < state.horizontal_offset {
-                        current_length += 1;
-                        continue;
-                    }
+                let grapheme_x = content_x + display_x - state.horizontal_offset;
 
-                    // Render content
-                    let point = buf.get_mut(buf_x, buf_y);
-                    if _char != '\n' && _char != '\t' {
-                        point.set_char(_char);
-                    }
-
-                    loop {
-                        // Search indicates
-                        if Self::within_mark(state, current_length, file_line as u16) ||
-                            indicates.indicates_find((
-                                current_length,
-                                file_line as u16
-                            ))
-                        {
-                            if self.is_cursor(current_length, file_line, state) {
-                                self.make_cursor(point, true);
-                                break;
-                            }
-
-                            self.color_reverse(buf, *style, buf_x, buf_y);
-                            break;
-                        }
-
-                        // Cursor
-                        if self.is_cursor(current_length, file_line, state) {
-                            self.make_cursor(point, state.mark_point.is_some());
-                            break;
-                        }
-
-                        point.set_style(*style);
-                        break;
-                    }
+                // Stop rendering current line.
+                // The wide grapheme crossing the right edge is not rendered.
+                if grapheme_x + width > area.width as usize {
+                    break;
+                }
 
-                    current_length += 1;
+                let highlighted = Self::within_mark(state, current_length, file_line) ||
+                    indicates.indicates_find((current_length, file_line));
+                let is_cursor = self.is_cursor(current_length, file_line, state);
 
-                    // Deal with the display of tabs
-                    if _char == '\t' {
-                        for _ in 0..4 {
-                            buf_x += 1;
+                for x in grapheme_x..(grapheme_x + width) {
+                    let x = x as u16;
+                    let point = buf.get_mut(x, buf_y);
 
-                            if buf_x < area.width &&
-                                Self::within_mark(state, current_length, file_line as u16)
-                            {
-                                self.color_reverse(buf, *style, buf_x, buf_y);
-                            }
+                    if x as usize == grapheme_x && grapheme != "\n" && grapheme != "\t" {
+                        point.set_symbol(grapheme);
+                    }
 
-                            // Avoid out of range panic
-                            if buf_x == area.width {
-                                break 'line;
-                            }
-                        }
+                    // Cursor, only the first cell of tab is rendered as cursor
+                    if is_cursor && (x as usize == grapheme_x || grapheme != "\t") {
+                        self.make_cursor(point, highlighted || state.mark_point.is_some());
+                        continue;
+                    }
 
+                    // Marked region & search indicates
+                    if highlighted {
+                        self.color_reverse(buf, *style, x, buf_y);
                         continue;
                     }
 
-                    buf_x += 1;
+                    point.set_style(*style);
                 }
+
+                current_length += 1;
+                display_x += width;
             }
             
             buf_y += 1;
@@ -9,7 +9,7 @@ use tokio::runtime::Runtime;
 
 use crate::{app::App, error::{AppError, AppResult}};
 
-use super::{info_line::InfoLine, Editor};
+use super::{info_line::InfoLine, Editor, HexView};
 
 pub fn main_frame(frame: &mut Frame, app: &mut App, rt: &Runtime) -> AppResult<()> {
     // TODO: Display range modify.
@@ -24,6 +24,15 @@ pub fn main_frame(frame: &mut Frame, app: &mut App, rt: &Runtime) -> AppResult<(
 
     // Update the content used to render
     let to_update = app.editor_state.update(main_layout[0]);
+
+    // The hex view has no horizontal scrolling.
+    if app.file_state.hex().is_none() {
+        let cursor_range = rt.block_on(
+            app.file_state.display_range(app.editor_state.cursor(), app.options().tab_width)
+        );
+        app.editor_state.update_horizontal(main_layout[0], cursor_range);
+    }
+
     if app.update_stylized || to_update {
         rt.block_on(async {
             app.file_state.refresh_stylized(
@@ -45,22 +54,33 @@ pub fn main_frame(frame: &mut Frame, app: &mut App, rt: &Runtime) -> AppResult<(
     }
 
     // Render frame
+    let info_line = InfoLine::from((&mut *app, &*rt));
+    frame.render_widget(info_line, main_layout[1]);
+
+    if let Some(hex) = app.file_state.hex() {
+        let hex_view = HexView::new(hex, app.get_bg()?, app.app_errors.empty());
+        frame.render_stateful_widget(
+            hex_view,
+            main_layout[0],
+            &mut app.editor_state
+        );
+
+        return Ok(())
+    }
+
     let editor = Editor::new(
         Arc::clone(app.file_state.stylized_ref()),
         Arc::clone(app.search_ref()),
         app.get_bg()?,
+        app.options().tab_width,
         app.app_errors.empty()
     );
 
-    let info_line = InfoLine::from((&mut *app, &*rt));
-
     frame.render_stateful_widget(
         editor,
         main_layout[0],
         &mut app.editor_state
     );
 
-    frame.render_widget(info_line, main_layout[1]);
-
     Ok(())
 }
@@ -0,0 +1,133 @@
+// Hex View
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::StatefulWidget
+};
+
+use crate::fs::HexBuffer;
+
+use super::EditorState;
+
+/// The width of offset column & the delimiter after it.
+const OFFSET_WIDTH: u16 = 11;
+
+/// The width of hex pane & the delimiter after it.
+const HEX_PANE_WIDTH: u16 = HexBuffer::ROW_SIZE as u16 * 3 + 3;
+
+/// The part of cursor in hex view, the byte is indicated by the cursor of EditorState.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HexCursor {
+    pub low_nibble: bool,
+    pub ascii_pane: bool        // Whether editing the chars rather than hex digits
+}
+
+/// The widget showing raw bytes of file, which has offset column, hex pane & ASCII pane.
+#[derive(Debug)]
+pub struct HexView<'a> {
+    hex: &'a HexBuffer,
+    background_color: Color,
+    render_cursor: bool
+}
+
+impl<'a> HexView<'a> {
+    pub fn new(hex: &'a HexBuffer, bg: Color, render_cursor: bool) -> Self {
+        Self { hex, background_color: bg, render_cursor }
+    }
+
+    /// Get the horizontal position of the byte at `col` in hex pane.
+    fn hex_x(col: usize) -> u16 {
+        let x = OFFSET_WIDTH + col as u16 * 3;
+
+        // Bytes are separated into two groups
+        if col >= HexBuffer::ROW_SIZE / 2 {
+            x + 1
+        } else {
+            x
+        }
+    }
+
+    fn ascii_char(byte: u8) -> char {
+        if byte.is_ascii_graphic() || byte == b' ' {
+            byte as char
+        } else {
+            '.'
+        }
+    }
+
+    fn set_char(buf: &mut Buffer, area: Rect, x: u16, y: u16, _char: char, style: Style) {
+        if x < area.width {
+            buf.get_mut(area.x + x, area.y + y)
+                .set_char(_char)
+                .set_style(style);
+        }
+    }
+}
+
+impl StatefulWidget for HexView<'_> {
+    type State = EditorState;
+
+    fn render(
+        self,
+        area: Rect,
+        buf: &mut Buffer,
+        state: &mut Self::State
+    )
+    {
+        let cursor = state.cursor();
+        let cursor_style = Style::default().fg(self.background_color).bg(Color::White);
+        let pair_style = Style::default().bg(Color::DarkGray);
+
+        let rows = (state.offset()..self.hex.rows()).take(area.height as usize);
+        for (buf_y, row) in rows.enumerate() {
+            let buf_y = buf_y as u16;
+
+            // Render offset
+            let offset_style = if row == cursor.1 {
+                Style::default().fg(Color::Black).bg(Color::White)
+            } else {
+                Style::default()
+            };
+            let offset = format!("{:08X}", row * HexBuffer::ROW_SIZE);
+            for (x, _char) in offset.chars().enumerate() {
+                Self::set_char(buf, area, x as u16, buf_y, _char, offset_style);
+            }
+
+            Self::set_char(buf, area, OFFSET_WIDTH - 2, buf_y, '|', Style::default());
+            Self::set_char(buf, area, OFFSET_WIDTH + HEX_PANE_WIDTH - 2, buf_y, '|', Style::default());
+
+            // Render bytes
+            for (col, byte) in self.hex.row(row).iter().enumerate() {
+                let is_cursor = self.render_cursor && (col, row) == cursor;
+                let digits = format!("{:02X}", byte);
+
+                for (idx, digit) in digits.chars().enumerate() {
+                    let style = match is_cursor {
+                        true if state.hex_cursor.ascii_pane => pair_style,
+                        true if state.hex_cursor.low_nibble == (idx == 1) => cursor_style,
+                        _ => Style::default(),
+                    };
+
+                    Self::set_char(buf, area, Self::hex_x(col) + idx as u16, buf_y, digit, style);
+                }
+
+                let style = match is_cursor {
+                    true if state.hex_cursor.ascii_pane => cursor_style,
+                    true => pair_style,
+                    false => Style::default(),
+                };
+
+                Self::set_char(
+                    buf,
+                    area,
+                    OFFSET_WIDTH + HEX_PANE_WIDTH + col as u16,
+                    buf_y,
+                    Self::ascii_char(*byte),
+                    style
+                );
+            }
+        }
+    }
+}
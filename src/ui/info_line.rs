@@ -9,7 +9,11 @@ use ratatui::{
 
 use tokio::runtime::Runtime;
 
-use crate::{app::App, command::CommandPrior, ui::ModalType};
+use crate::{
+    app::{App, BUFFER_KEYS},
+    command::CommandPrior,
+    ui::ModalType
+};
 
 use super::CommandEdit;
 
@@ -20,14 +24,37 @@ pub struct InfoLine<'a> {
 }
 
 impl<'a> InfoLine<'a> {
-    fn make_yes_span() -> Span<'a> {
+    fn make_key_span(key: String) -> Span<'a> {
         Span::styled(
-            "y",
+            key,
             Style::new()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD)
         )
     }
+
+    /// The keys & their meanings for answering current asking.
+    fn ask_choices(app: &App, rt: &Runtime) -> Vec<(String, String)> {
+        let choices = match app.prior_command {
+            CommandPrior::Recover => vec![("r", "recover"), ("d", "diff"), ("x", "discard")],
+            CommandPrior::DiskChange => vec![("r", "reload"), ("o", "overwrite"), ("d", "diff")],
+
+            // Each buffer is picked with its key, the modified ones are marked with '*'.
+            CommandPrior::PickBuffer => return BUFFER_KEYS.chars()
+                .zip(rt.block_on(app.buffer_names()))
+                .map(|(key, (name, modified))| (
+                    key.to_string(),
+                    if modified { format!("{}*", name) } else { name }
+                ))
+                .collect(),
+
+            _ => vec![("y", "yes")]
+        };
+
+        choices.into_iter()
+            .map(|(key, choice)| (String::from(key), String::from(choice)))
+            .collect()
+    }
 }
 
 impl<'a> From<(&mut App, &Runtime)> for InfoLine<'a> {
@@ -54,15 +81,29 @@ impl<'a> From<(&mut App, &Runtime)> for InfoLine<'a> {
             if let Some(ref _msg) = app.ask_msg {
                 msg.push(Span::from(_msg.to_owned()));
                 msg.push(Span::from(" ("));
-                msg.push(Self::make_yes_span());
-                msg.push(Span::from(" for yes)"));
+
+                for (idx, (key, choice)) in Self::ask_choices(app, rt).into_iter().enumerate() {
+                    if idx > 0 {
+                        msg.push(Span::from(", "));
+                    }
+
+                    msg.push(Self::make_key_span(key));
+                    msg.push(Span::from(format!(" for {}", choice)));
+                }
+
+                msg.push(Span::from(")"));
 
                 break;
             }
 
             // Command Line Editing
-            if let CommandEdit::Some(ref content, ref cursor, _) = app.command_edit {
-                if *cursor == content.len() {
+            if let CommandEdit::Some(ref content, ref cursor, ref cmd) = app.command_edit {
+                msg.push(Span::styled(
+                    CommandEdit::prompt(cmd),
+                    Style::new().add_modifier(Modifier::BOLD)
+                ));
+
+                if *cursor == content.chars().count() {
                     msg.push(Span::from(content.to_owned()));
                     msg.push(Span::styled(" ", Style::new().bg(Color::White)));
 
@@ -95,6 +136,32 @@ impl<'a> From<(&mut App, &Runtime)> for InfoLine<'a> {
                 ));
             }
 
+            // The register selected for the next command
+            if let Some(name) = app.registers.selected() {
+                msg.push(Span::styled(
+                    format!(" \"{}", name),
+                    Style::new().add_modifier(Modifier::BOLD)
+                ));
+            }
+
+            // The count typed for the next command
+            if let Some(count) = app.count {
+                msg.push(Span::styled(
+                    format!(" {}", count),
+                    Style::new().add_modifier(Modifier::BOLD)
+                ));
+            }
+
+            let buffers = app.buffers();
+            if buffers.len() > 1 {
+                sign.push(Span::from(format!(
+                    "[{}/{}] {} ",
+                    buffers.active() + 1,
+                    buffers.len(),
+                    app.file_state.name()
+                )));
+            }
+
             let search_ref = app.search_ref().blocking_lock();
             if search_ref.has_history() && search_ref.selected() != None {
                 sign.push(Span::from(format!(
@@ -104,6 +171,16 @@ impl<'a> From<(&mut App, &Runtime)> for InfoLine<'a> {
                 )));
             }
 
+            sign.push(Span::from(format!(
+                "{}{}{}{}{} {} ",
+                if app.file_state.is_readonly() { "[RO] " } else { "" },
+                if app.file_state.hex().is_some() { "[Hex] " } else { "" },
+                if app.file_state.is_new_file() { "[New] " } else { "" },
+                if app.file_state.final_newline() { "" } else { "[noeol] " },
+                app.file_state.encoding().name(),
+                app.file_state.line_ending().name()
+            )));
+
             // File modification state
             sign.push(Span::styled(
                 if rt.block_on(app.file_state.not_save()) {
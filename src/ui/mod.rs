@@ -1,10 +1,12 @@
 mod frame;
 mod editor;
 mod modal;
+mod hex_view;
 mod info_line;
 mod command_edit;
 
 pub use editor::*;
 pub use modal::*;
 pub use frame::main_frame;
+pub use hex_view::{HexCursor, HexView};
 pub use command_edit::CommandEdit;
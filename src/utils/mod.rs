@@ -1,6 +1,20 @@
 // Utils
 
 use std::mem::swap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use unicode_width::UnicodeWidthStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(test)]
+mod temp_dir;
+
+#[cfg(test)]
+pub use temp_dir::TempDir;
+
+/// The distance between tab stops by default.
+pub const DEFAULT_TAB_WIDTH: usize = 4;
 
 pub fn cursor_compare_swap<T>(small: &mut (T, T), big: &mut (T, T))
 where T: PartialEq + PartialOrd + Copy
@@ -14,3 +28,208 @@ where T: PartialEq + PartialOrd + Copy
         swap(small, big);
     }
 }
+
+/// Get the count of grapheme clusters, which is the length of a line for cursor.
+pub fn grapheme_len(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// Convert grapheme column into byte index.
+/// The column out of range points to the end of text.
+pub fn grapheme_to_byte(text: &str, col: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(col)
+        .map(|(idx, _)| idx)
+        .unwrap_or(text.len())
+}
+
+/// Convert byte index into grapheme column.
+/// The index within a grapheme cluster will be rounded up to the next cluster
+/// when `round_up` is true, otherwise rounded down to the cluster itself.
+pub fn byte_to_grapheme(text: &str, byte_idx: usize, round_up: bool) -> usize {
+    text.grapheme_indices(true)
+        .take_while(|(idx, grapheme)| {
+            if round_up {
+                *idx < byte_idx
+            } else {
+                idx + grapheme.len() <= byte_idx
+            }
+        })
+        .count()
+}
+
+/// Get the count of terminal cells taken by a grapheme cluster at display column `display_x`.
+/// A tab extends to the next tab stop.
+/// Each grapheme takes one cell at least, thus the cursor can be placed on it.
+pub fn display_width(grapheme: &str, display_x: usize, tab_width: usize) -> usize {
+    match grapheme {
+        "\t" => tab_width - display_x % tab_width,
+        "\n" => 1,
+        _ => grapheme.width().max(1)
+    }
+}
+
+/// Get the range of display columns taken by the grapheme at column `col`.
+/// The grapheme out of range is regarded as a single cell after the end of line.
+pub fn display_range(line: &str, col: usize, tab_width: usize) -> Range<usize> {
+    let mut start = 0;
+
+    for (idx, grapheme) in line.graphemes(true).enumerate() {
+        let width = display_width(grapheme, start, tab_width);
+
+        if idx == col {
+            return start..(start + width)
+        }
+
+        start += width;
+    }
+
+    start..(start + 1)
+}
+
+/// Get the column of grapheme taking display column `display_x`.
+/// The display column out of range points to the last grapheme.
+pub fn display_to_grapheme(line: &str, display_x: usize, tab_width: usize) -> usize {
+    let mut start = 0;
+    let mut col = 0;
+
+    for grapheme in line.graphemes(true) {
+        start += display_width(grapheme, start, tab_width);
+
+        if start > display_x {
+            return col
+        }
+
+        col += 1;
+    }
+
+    col.saturating_sub(1)
+}
+
+pub fn home_dir() -> PathBuf {
+    if let Ok(home) = std::env::var("HOME") {
+        return PathBuf::from(home)
+    }
+
+    let user = std::env::var("USER")
+        .expect("Failed to get user name!");
+
+    if &user == "root" {
+        PathBuf::from("/root")
+    } else {
+        PathBuf::from(format!("/home/{}", user))
+    }
+}
+
+/// Get the directory storing the state of this app, such as swap files.
+pub fn state_dir() -> PathBuf {
+    let state_home = match std::env::var("XDG_STATE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home_dir().join(".local/state"),
+    };
+
+    state_home.join("springhan/tetor")
+}
+
+/// Get the indexes of lines in `new` which are not in the longest common subsequence
+/// of `old` and `new`, i.e. the changed or added lines.
+pub fn changed_lines(old: &[&str], new: &[&str]) -> Vec<usize> {
+    // The lines table of larger content costs too much memory.
+    const MAX_TABLE_SIZE: usize = 4_000_000;
+
+    let prefix = old.iter()
+        .zip(new.iter())
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old = &old[prefix..(old.len() - suffix)];
+    let new = &new[prefix..(new.len() - suffix)];
+
+    if old.is_empty() || old.len() * new.len() > MAX_TABLE_SIZE {
+        return (prefix..(prefix + new.len())).collect()
+    }
+
+    // The length of common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut table = vec![0u32; (old.len() + 1) * width];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * width + j] = if old[i] == new[j] {
+                table[(i + 1) * width + j + 1] + 1
+            } else {
+                table[(i + 1) * width + j].max(table[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changed = Vec::new();
+
+    while j < new.len() {
+        if i < old.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && table[(i + 1) * width + j] >= table[i * width + j + 1] {
+            i += 1;
+        } else {
+            changed.push(prefix + j);
+            j += 1;
+        }
+    }
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grapheme_test() {
+        // 'e' with combining acute accent, and a family emoji joined by ZWJ
+        let text = "ae\u{301}\u{1F468}\u{200D}\u{1F469}x\n";
+        assert_eq!(grapheme_len(text), 5);
+        assert_eq!(grapheme_to_byte(text, 2), 4);
+        assert_eq!(grapheme_to_byte(text, 9), text.len());
+
+        assert_eq!(byte_to_grapheme(text, 4, false), 2);
+        assert_eq!(byte_to_grapheme(text, 2, false), 1);
+        assert_eq!(byte_to_grapheme(text, 2, true), 2);
+        assert_eq!(byte_to_grapheme(text, text.len(), true), 5);
+    }
+
+    #[test]
+    fn display_width_test() {
+        let text = "a世e\u{301}\u{1F468}\u{200D}\u{1F469}\tb\n";
+        assert_eq!(display_range(text, 0, 4), 0..1);
+        assert_eq!(display_range(text, 1, 4), 1..3);
+        assert_eq!(display_range(text, 2, 4), 3..4);
+        assert_eq!(display_range(text, 3, 4), 4..6);
+        assert_eq!(display_range(text, 4, 4), 6..8);
+        assert_eq!(display_range(text, 4, 8), 6..8);
+        assert_eq!(display_range(text, 6, 4), 9..10);
+        assert_eq!(display_range(text, 10, 4), 10..11);
+
+        // Each tab extends to the next tab stop.
+        assert_eq!(display_range("\tab\tc\n", 3, 4), 6..8);
+        assert_eq!(display_range("\tab\tc\n", 3, 3), 5..6);
+
+        assert_eq!(display_to_grapheme(text, 7, 4), 4);
+        assert_eq!(display_to_grapheme(text, 8, 4), 5);
+        assert_eq!(display_to_grapheme(text, 20, 4), 6);
+    }
+
+    #[test]
+    fn changed_lines_test() {
+        let old = ["a\n", "b\n", "c\n", "d\n"];
+        assert_eq!(changed_lines(&old, &["a\n", "x\n", "c\n", "d\n", "e\n"]), vec![1, 4]);
+        assert_eq!(changed_lines(&old, &["b\n", "c\n", "y\n", "d\n"]), vec![2]);
+        assert_eq!(changed_lines(&[], &["a\n"]), vec![0]);
+        assert!(changed_lines(&old, &old).is_empty());
+    }
+}